@@ -1,62 +1,753 @@
-// #![feature(iter_chain)]
-
-use std::env::args;
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::sleep,
+    time::{Duration, Instant},
+};
 
+use clap::{
+    builder::RangedU64ValueParser, error::ErrorKind, parser::ValueSource, Args, CommandFactory,
+    FromArgMatches, Parser, Subcommand, ValueEnum,
+};
 use num_format::ToFormattedString;
 use shelf_viewer::{
+    blockdev::BlockResolver,
+    btrfs::BtrfsList,
     console_widget::{
-        ConsoleViewer, SlotLabel, SlotLine, SlotPrintOrder, SlotState, ALERT_LOCATING,
+        split_panels, ConsoleViewer, Legend, LegendRow, SlotLabel, SlotLine, SlotPrintOrder,
+        SlotState, ALERT_FAULT, ALERT_LOCATING, ALERT_PREDICTED_FAILURE, ALERT_SCANNING,
     },
-    enclosure::Enclosure,
-    err::SResult,
+    enclosure::{Enclosure, LocateGuard, Slot, SlotTarget},
+    err::{SError, SResult},
+    holders::Holders,
+    layout::{builtin_layout, ChassisLayout, LayoutConfig, PanelLayout, DEFAULT_LAYOUT_CONFIG},
     lsblk::{Lsblk, LsblkEntry},
-    zfs::ZfsList,
+    lvm::LvmList,
+    md::{MdList, MdRole},
+    sysfs::{SysfsRoot, DEFAULT_SYSFS_ROOT},
+    utils::{parse_duration, PrivacyNames},
+    zfs::{ZfsList, ZfsListPool},
     LOCALE,
 };
 
 // "On The Box" size, not computer Gibibytes
 const GIGABYTE: usize = 1000usize.pow(3);
 
+// Unknown chassis
+const DEFAULT_WIDTH: usize = 4;
+const DEFAULT_ORDER: SlotPrintOrder = SlotPrintOrder::BOTTOM_LEFT_GOING_UP;
+
+/// Displays your Disk Shelf / JBOD as it looks physically
+#[derive(Parser)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    show: ShowArgs,
+
+    /// Where sysfs is mounted, eg /host/sys inside a container or a captured copy.
+    /// dev and proc are read from the same parent
+    #[arg(long, global = true, env = "SHELF_VIEWER_SYSFS", default_value = DEFAULT_SYSFS_ROOT)]
+    sysfs: PathBuf,
+
+    /// Only these enclosures, by enc_id path or logical id
+    #[arg(long = "enclosure", global = true)]
+    enclosures: Vec<String>,
+}
+
+impl Cli {
+    /// Like `try_parse_from`, but the default view's flags are rejected next to a subcommand.
+    /// clap's args_conflicts_with_subcommands would reject the global flags too
+    fn try_parse_checked<I, T>(args: I) -> Result<Cli, clap::Error>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<std::ffi::OsString> + Clone,
+    {
+        let mut command = Cli::command();
+        let matches = command.try_get_matches_from_mut(args)?;
+        if let Some((name, _)) = matches.subcommand() {
+            let show_command = ShowArgs::augment_args(clap::Command::new("show"));
+            let given = show_command.get_arguments().find(|arg| {
+                matches.value_source(arg.get_id().as_str()) == Some(ValueSource::CommandLine)
+            });
+            if let Some(arg) = given {
+                return Err(command.error(
+                    ErrorKind::ArgumentConflict,
+                    format!(
+                        "the argument '--{}' cannot be used with the subcommand '{}'",
+                        arg.get_long().unwrap_or_default(),
+                        name
+                    ),
+                ));
+            }
+        }
+        Cli::from_arg_matches(&matches).map_err(|err| err.format(&mut command))
+    }
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Draw every enclosure (default)
+    Show(ShowArgs),
+    /// Turn the locate light on or off
+    Locate(LocateArgs),
+    /// Turn the fault light on or off
+    Fault(LedArgs),
+    /// Print the enclosure and slot holding a device
+    Find {
+        /// enc_id/slot, block device, or WWID
+        target: String,
+    },
+    /// Print every slot as tab separated values
+    Export,
+}
+
+#[derive(Args)]
+struct ShowArgs {
+    /// Slots per row, overrides the chassis layout
+    #[arg(long, value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+    width: Option<usize>,
+
+    /// Corner of slot 0 and first direction, eg bottom-left-going-up, top-left-going-right
+    /// or top-right-going-left-serpentine. Overrides the chassis layout
+    #[arg(long)]
+    order: Option<SlotPrintOrder>,
+
+    /// Extra lines under each slot
+    #[arg(long, value_delimiter = ',')]
+    fields: Vec<Field>,
+
+    /// For screenshots, don't leak exact WWNs, Models, and pool names
+    #[arg(long)]
+    privacy: bool,
+
+    /// Print what is missing instead of blanks
+    #[arg(long)]
+    show_missing: bool,
+
+    /// Physical shelf geometry for shelves without a built-in layout
+    #[arg(long, default_value = DEFAULT_LAYOUT_CONFIG)]
+    layouts: PathBuf,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Field {
+    Wwid,
+    Wwn,
+    Model,
+    Status,
+    Size,
+    /// Pool, parent vdev, and ZFS health like `tank / raidz2-1 / FAULTED 3 cksum`
+    Zfs,
+}
+
+#[derive(Args)]
+struct LedArgs {
+    /// enc_id/slot, block device, or WWID
+    target: String,
+
+    #[arg(default_value = "on")]
+    state: LedState,
+}
+
+#[derive(Args)]
+struct LocateArgs {
+    /// enc_id/slot, block device, or WWID. With --zfs a pool, vdev, or pool/vdev
+    #[arg(required_unless_present = "clear_all")]
+    target: Option<String>,
+
+    /// --for always turns it on and puts it back after
+    #[arg(default_value = "on", conflicts_with = "duration")]
+    state: LedState,
+
+    /// Light for this long then put it back, eg 30s 10m 2h
+    #[arg(long = "for", value_parser = parse_duration_arg)]
+    duration: Option<Duration>,
+
+    /// Target is a ZFS pool or vdev, light every member
+    #[arg(long)]
+    zfs: bool,
+
+    /// Turn off every locate light
+    #[arg(long, conflicts_with_all = ["target", "zfs", "duration"])]
+    clear_all: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum LedState {
+    On,
+    Off,
+}
+
+impl LedState {
+    fn enabled(&self) -> bool {
+        *self == LedState::On
+    }
+}
+
+fn parse_duration_arg(value: &str) -> Result<Duration, String> {
+    let duration = parse_duration(value).ok_or(format!("invalid duration {}", value))?;
+    // the deadline has to fit in an Instant
+    Instant::now()
+        .checked_add(duration)
+        .ok_or(format!("duration {} is too long", value))?;
+    Ok(duration)
+}
+
 fn main() {
     inner_main().unwrap()
 }
 
 fn inner_main() -> SResult<()> {
-    let args: Vec<String> = args().collect();
-    println!("args {}", args.join(","));
-    let width: usize = args[1].parse().expect("need width arg");
+    let cli = Cli::try_parse_checked(std::env::args_os()).unwrap_or_else(|err| err.exit());
+    let sysfs = SysfsRoot::new(&cli.sysfs);
+
+    match cli.command {
+        None => show(&sysfs, &cli.enclosures, &cli.show),
+        Some(Command::Show(show_args)) => show(&sysfs, &cli.enclosures, &show_args),
+        Some(Command::Locate(locate_args)) => locate(&sysfs, &cli.enclosures, &locate_args),
+        Some(Command::Fault(led_args)) => fault(&sysfs, &cli.enclosures, &led_args),
+        Some(Command::Find { target }) => find(&sysfs, &cli.enclosures, &target),
+        Some(Command::Export) => export(&sysfs, &cli.enclosures),
+    }
+}
+
+fn load_enclosures(sysfs: &SysfsRoot, filter: &[String]) -> SResult<Vec<Enclosure>> {
+    let mut enclosures = Enclosure::load_all(sysfs)?;
+    if !filter.is_empty() {
+        enclosures.retain(|enclosure| filter.iter().any(|id| enclosure.has_id(id)));
+    }
+    Ok(enclosures)
+}
 
-    let zfs_list = ZfsList::execute();
+fn show(sysfs: &SysfsRoot, filter: &[String], args: &ShowArgs) -> SResult<()> {
+    let layout_config = LayoutConfig::load(&args.layouts)?;
+    let mut owners = DiskOwners::load(sysfs)?;
+    if args.privacy {
+        owners.anonymize();
+    }
+    owners.zfs_list.print_summary();
     let lsblk_list = Lsblk::execute();
 
-    for enclosure in Enclosure::load_all()? {
-        load_enclosure(enclosure, width, &zfs_list, &lsblk_list);
+    let enclosures = load_enclosures(sysfs, filter)?;
+    let layouts = enclosures
+        .iter()
+        .map(|enclosure| find_layout(enclosure, &layout_config))
+        .collect::<SResult<Vec<Option<ChassisLayout>>>>()?;
+    // drawn as a panel of another enclosure's chassis
+    let panel_enclosures: Vec<&str> = layouts
+        .iter()
+        .flatten()
+        .flat_map(|layout| &layout.panels)
+        .filter_map(|panel| panel.enclosure.as_deref())
+        .collect();
+
+    for (enclosure, layout) in enclosures.iter().zip(layouts.iter()) {
+        if panel_enclosures.iter().any(|id| enclosure.has_id(id)) {
+            continue;
+        }
+        load_enclosure(
+            enclosure,
+            layout.as_ref(),
+            &enclosures,
+            args,
+            &owners,
+            &lsblk_list,
+        )?;
     }
 
     Ok(())
 }
 
+/// Pools, arrays and volume groups a disk can belong to
+struct DiskOwners {
+    zfs_list: ZfsList,
+    md_list: MdList,
+    lvm_list: LvmList,
+    btrfs_list: BtrfsList,
+    /// Anything else, through partitions, dm-crypt and mounts
+    holders: Holders,
+}
+
+impl DiskOwners {
+    fn load(sysfs: &SysfsRoot) -> SResult<Self> {
+        let resolver = BlockResolver::from_sysfs(sysfs);
+        Ok(Self {
+            zfs_list: zfs_list_or_empty(sysfs),
+            md_list: MdList::load(sysfs, &resolver)?,
+            lvm_list: LvmList::execute(&resolver).unwrap_or_else(|err| {
+                println!("[E] {}", err);
+                LvmList::default()
+            }),
+            btrfs_list: BtrfsList::load(sysfs, &resolver)?,
+            holders: Holders::load(sysfs, &resolver)?,
+        })
+    }
+
+    /// For screenshots, every name a group key or owner line is made of
+    fn anonymize(&mut self) {
+        let mut names = PrivacyNames::default();
+        for pool in &mut self.zfs_list.pools {
+            pool.pool_name = names.name("pool", &pool.pool_name);
+        }
+        for array in &mut self.md_list.arrays {
+            array.name = names.name("md", &array.name);
+        }
+        for vg_name in self.lvm_list.pvs.iter_mut().flat_map(|pv| &mut pv.vg_name) {
+            *vg_name = names.name("vg", vg_name);
+        }
+        for filesystem in &mut self.btrfs_list.filesystems {
+            filesystem.label = Some(names.name("btrfs", filesystem.name()));
+        }
+        self.holders.anonymize(names);
+    }
+}
+
+/// Shelves still draw without ZFS installed
+fn zfs_list_or_empty(sysfs: &SysfsRoot) -> ZfsList {
+    ZfsList::execute(&BlockResolver::from_sysfs(sysfs)).unwrap_or_else(|err| {
+        println!("[E] {}", err);
+        ZfsList::default()
+    })
+}
+
+fn find_layout(
+    enclosure: &Enclosure,
+    layout_config: &LayoutConfig,
+) -> SResult<Option<ChassisLayout>> {
+    let slots = enclosure.slots()?.len();
+    let vendor = enclosure.device_vendor().unwrap_or_default();
+    let model = enclosure.device_model().unwrap_or_default();
+    Ok(
+        match layout_config.find(enclosure, &vendor, &model, slots)? {
+            Some(layout) => Some(layout),
+            None => builtin_layout(&vendor, &model, slots),
+        },
+    )
+}
+
+fn locate(sysfs: &SysfsRoot, filter: &[String], args: &LocateArgs) -> SResult<()> {
+    if args.clear_all {
+        return locate_clear(sysfs, filter);
+    }
+    let target = args.target.as_ref().unwrap();
+
+    let enclosures = load_enclosures(sysfs, filter)?;
+    let slots = if args.zfs {
+        find_zfs_slots(sysfs, &enclosures, target)?
+    } else {
+        vec![SlotTarget::parse(target, &BlockResolver::from_sysfs(sysfs)).find(&enclosures)?]
+    };
+
+    if let Some(duration) = args.duration {
+        return locate_for(&slots, duration);
+    }
+    for slot in slots {
+        slot.set_locate(args.state.enabled())?;
+        print_slot_led(&slot, "locate", args.state.enabled());
+    }
+    Ok(())
+}
+
+fn fault(sysfs: &SysfsRoot, filter: &[String], args: &LedArgs) -> SResult<()> {
+    let enclosures = load_enclosures(sysfs, filter)?;
+    let slot =
+        SlotTarget::parse(&args.target, &BlockResolver::from_sysfs(sysfs)).find(&enclosures)?;
+    slot.set_fault(args.state.enabled())?;
+    print_slot_led(&slot, "fault", args.state.enabled());
+    Ok(())
+}
+
+fn print_slot_led(slot: &Slot, led_name: &str, enabled: bool) {
+    println!(
+        "{} slot {} {} {} {}",
+        slot.enc_id(),
+        slot.slot_id(),
+        slot.block_name().unwrap_or_default(),
+        led_name,
+        on_off(enabled)
+    );
+}
+
+/// Every slot holding a member of a ZFS pool `tank`, vdev `raidz2-1`, or `tank/raidz2-1`
+fn find_zfs_slots(sysfs: &SysfsRoot, enclosures: &[Enclosure], name: &str) -> SResult<Vec<Slot>> {
+    let zfs_list = ZfsList::execute(&BlockResolver::from_sysfs(sysfs))?;
+    zfs_list.print_summary();
+    let leaf_names = zfs_list.leaf_names(name)?;
+    if leaf_names.is_empty() {
+        return Err(SError::SlotNotFound {
+            target: name.to_string(),
+        });
+    }
+
+    let mut slots = Vec::new();
+    for enclosure in enclosures {
+        for slot in enclosure.slots()?.into_values() {
+            if slot
+                .block_name()
+                .is_some_and(|block_name| leaf_names.contains(&block_name.as_str()))
+            {
+                slots.push(slot);
+            }
+        }
+    }
+
+    for leaf_name in leaf_names {
+        if !slots
+            .iter()
+            .any(|slot| slot.block_name().as_deref() == Some(leaf_name))
+        {
+            println!("[E] {} {} is not in any enclosure", name, leaf_name);
+        }
+    }
+    Ok(slots)
+}
+
+fn locate_clear(sysfs: &SysfsRoot, filter: &[String]) -> SResult<()> {
+    for enclosure in load_enclosures(sysfs, filter)? {
+        for slot in enclosure.slots()?.into_values() {
+            if slot.is_locating() {
+                slot.set_locate(false)?;
+                print_slot_led(&slot, "locate", false);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn on_off(enabled: bool) -> &'static str {
+    if enabled {
+        "on"
+    } else {
+        "off"
+    }
+}
+
+/// Stays in the foreground until the time runs out or Ctrl-C, then puts the lights back
+fn locate_for(slots: &[Slot], duration: Duration) -> SResult<()> {
+    let stop = Arc::new(AtomicBool::new(false));
+    let handler_stop = stop.clone();
+    ctrlc::set_handler(move || handler_stop.store(true, Ordering::SeqCst))
+        .expect("failed to set Ctrl-C handler");
+
+    let mut guards = Vec::with_capacity(slots.len());
+    for slot in slots {
+        guards.push(LocateGuard::new(slot)?);
+        println!(
+            "{} slot {} {} locate on for {}s, Ctrl-C to stop early",
+            slot.enc_id(),
+            slot.slot_id(),
+            slot.block_name().unwrap_or_default(),
+            duration.as_secs()
+        );
+    }
+
+    // checked when parsing, until Ctrl-C if it somehow doesn't fit
+    let deadline = Instant::now().checked_add(duration);
+    while deadline.is_none_or(|deadline| Instant::now() < deadline) && !stop.load(Ordering::SeqCst)
+    {
+        sleep(Duration::from_millis(200));
+    }
+
+    for (slot, guard) in slots.iter().zip(guards) {
+        let previous = guard.previous();
+        guard.restore()?;
+        println!(
+            "{} slot {} locate restored to {}",
+            slot.enc_id(),
+            slot.slot_id(),
+            on_off(previous)
+        );
+    }
+    Ok(())
+}
+
+fn find(sysfs: &SysfsRoot, filter: &[String], target: &str) -> SResult<()> {
+    let enclosures = load_enclosures(sysfs, filter)?;
+    let slot = SlotTarget::parse(target, &BlockResolver::from_sysfs(sysfs)).find(&enclosures)?;
+    println!(
+        "{} slot {} ({}) {} {}",
+        slot.enc_id(),
+        slot.slot_id(),
+        slot.component(),
+        slot.block_name().unwrap_or_default(),
+        slot.device_wwid().unwrap_or_default()
+    );
+    Ok(())
+}
+
+fn export(sysfs: &SysfsRoot, filter: &[String]) -> SResult<()> {
+    let zfs_list = zfs_list_or_empty(sysfs);
+    zfs_list.print_summary();
+    let lsblk_list = Lsblk::execute();
+
+    println!("enclosure\tslot\tcomponent\tdevice\twwid\tmodel\tbytes\tstatus\tlocate\tfault\tzfs");
+    for enclosure in load_enclosures(sysfs, filter)? {
+        for slot in enclosure.slots()?.into_values() {
+            let block_name = slot.block_name();
+            let bytes = block_name
+                .as_ref()
+                .and_then(|block_name| lsblk_list.iter().find(|v| &v.device == block_name))
+                .map(|entry| entry.bytes.clone());
+            let pool = block_name
+                .as_ref()
+                .and_then(|block_name| zfs_list.find_vdev(block_name))
+                .map(|(pool, _)| pool.pool_name.clone());
+            let indicators = slot.indicators();
+            let row = [
+                enclosure.enc_id().to_string(),
+                slot.slot_id().to_string(),
+                slot.component().to_string(),
+                block_name.clone().unwrap_or_default(),
+                block_name
+                    .and_then(|_| slot.device_wwid())
+                    .unwrap_or_default(),
+                slot.device_model().unwrap_or_default(),
+                bytes.unwrap_or_default(),
+                slot.status()
+                    .map(|s| s.name().to_string())
+                    .unwrap_or_default(),
+                (indicators.locate as u8).to_string(),
+                (indicators.fault as u8).to_string(),
+                pool.unwrap_or_default(),
+            ];
+            println!("{}", row.join("\t"));
+        }
+    }
+    Ok(())
+}
+
 fn load_enclosure(
-    enclosure: Enclosure,
-    width: usize,
-    zfs_list: &ZfsList,
+    enclosure: &Enclosure,
+    layout: Option<&ChassisLayout>,
+    enclosures: &[Enclosure],
+    args: &ShowArgs,
+    owners: &DiskOwners,
     lsblk_list: &[LsblkEntry],
-) {
-    let slot_len = enclosure.slot_len().unwrap();
-    let mut states = Vec::with_capacity(slot_len);
-    let mut total_enclosure_bytes = 0;
-    for slot_id in 0..slot_len {
-        let slot = enclosure.slot(slot_id);
+) -> SResult<()> {
+    let not_found = |msg: &str| -> String {
+        if args.show_missing {
+            msg.into()
+        } else {
+            "".into()
+        }
+    };
+
+    let vendor = enclosure.device_vendor().unwrap_or(not_found("no_vendor"));
+    let model = enclosure.device_model().unwrap_or(not_found("no_model"));
+    let labels = layout.map(|layout| &layout.labels);
+    let (states, mut total_enclosure_bytes) =
+        slot_states(enclosure, labels, args, owners, lsblk_list)?;
+
+    let panels = match (layout, args.width) {
+        (Some(layout), None) => layout
+            .panels
+            .iter()
+            .map(|panel| PanelLayout {
+                order: args.order.unwrap_or(panel.order),
+                ..panel.clone()
+            })
+            .collect(),
+        (_, width) => vec![PanelLayout::with_width(
+            states.len(),
+            width.unwrap_or(DEFAULT_WIDTH),
+            args.order.unwrap_or(DEFAULT_ORDER),
+        )],
+    };
+
+    // rear backplanes with their own expander are another enclosure
+    let mut joined = Vec::new();
+    let panel_states = split_panels(&panels, states, |_, id| {
+        let Some(other) = enclosures.iter().find(|other| other.has_id(id)) else {
+            return Ok(None);
+        };
+        joined.push(other);
+        let (other_states, bytes) = slot_states(other, None, args, owners, lsblk_list)?;
+        total_enclosure_bytes += bytes;
+        Ok(Some(other_states))
+    })?;
+
+    let mut paths = if enclosure.paths().len() == 1 {
+        enclosure.enc_id().to_string()
+    } else {
+        enclosure
+            .paths()
+            .iter()
+            .map(|path| {
+                let state = enclosure.path_state(path).unwrap_or("?".into());
+                if path == enclosure.enc_id() {
+                    format!("{} [{}*]", path, state)
+                } else {
+                    format!("{} [{}]", path, state)
+                }
+            })
+            .collect::<Vec<String>>()
+            .join(" ")
+    };
+    for other in &joined {
+        paths.push_str(&format!(" + {}", other.enc_id()));
+    }
+    let title = match layout {
+        Some(layout) => format!("{} {} ({}) - {}", vendor, model, layout.name, paths),
+        None => format!("{} {} - {}", vendor, model, paths),
+    };
+
+    let mut shown = vec![enclosure];
+    shown.extend(joined);
+    let viewer = ConsoleViewer {
+        title: Some(title),
+        panels,
+        legend: Some(pool_legend(&shown, &owners.zfs_list)?),
+    };
+    viewer.print_panels(&panel_states.iter().map(Vec::as_slice).collect::<Vec<_>>());
+
+    println!(
+        "Total Size {} G",
+        (total_enclosure_bytes / GIGABYTE).to_formatted_string(LOCALE)
+    );
+    Ok(())
+}
 
-        let mut device_flag = None;
-        if slot.is_locating() {
-            device_flag = Some(ALERT_LOCATING);
+/// Every pool with a disk in the shelf, the disk count elsewhere finds pools spanning shelves
+fn pool_legend(shown: &[&Enclosure], zfs_list: &ZfsList) -> SResult<Legend> {
+    let mut block_names = Vec::new();
+    for enclosure in shown {
+        for slot in enclosure.slots()?.into_values() {
+            block_names.extend(slot.block_name());
         }
+    }
+
+    let mut rows = Vec::new();
+    for pool in &zfs_list.pools {
+        let disks = pool.disk_names();
+        let here = disks
+            .iter()
+            .filter(|disk| block_names.iter().any(|name| name == *disk))
+            .count();
+        if here == 0 {
+            continue;
+        }
+        let group_key = zfs_group_key(pool);
+        let percent = |value: Option<u64>| match value {
+            Some(value) => format!("{}%", value),
+            None => "-".to_string(),
+        };
+        let health = pool
+            .usage
+            .as_ref()
+            .map(|usage| &usage.health)
+            .or(pool.state.as_ref())
+            .map(|state| state.name().to_string())
+            .unwrap_or_default();
+        let mut cells = vec![pool.pool_name.clone(), health];
+        match &pool.usage {
+            Some(usage) => cells.extend([
+                percent(usage.cap),
+                percent(usage.frag),
+                format!(
+                    "{} / {} G",
+                    (usage.alloc as usize / GIGABYTE).to_formatted_string(LOCALE),
+                    (usage.size as usize / GIGABYTE).to_formatted_string(LOCALE)
+                ),
+            ]),
+            None => cells.extend(["-".to_string(), "-".to_string(), "-".to_string()]),
+        }
+        cells.push(here.to_string());
+        cells.push((disks.len() - here).to_string());
+        rows.push(LegendRow { group_key, cells });
+    }
+
+    Ok(Legend {
+        header: [
+            "pool",
+            "health",
+            "cap",
+            "frag",
+            "alloc / size",
+            "disks here",
+            "elsewhere",
+        ]
+        .map(String::from)
+        .to_vec(),
+        rows,
+    })
+}
+
+/// Slots of a pool share a color
+fn zfs_group_key(pool: &ZfsListPool) -> String {
+    format!("ZFS {}", pool.pool_name)
+}
+
+/// Cell contents for every slot and the total size of the disks
+fn slot_states(
+    enclosure: &Enclosure,
+    labels: Option<&HashMap<usize, String>>,
+    args: &ShowArgs,
+    owners: &DiskOwners,
+    lsblk_list: &[LsblkEntry],
+) -> SResult<(Vec<SlotState>, usize)> {
+    let not_found = |msg: &str| -> String {
+        if args.show_missing {
+            msg.into()
+        } else {
+            "".into()
+        }
+    };
+
+    let slots = enclosure.slots()?;
+    let mut states = Vec::with_capacity(slots.len());
+    let mut total_enclosure_bytes = 0;
+    for (slot_id, slot) in slots {
+        let status = slot.status();
+        let indicators = slot.indicators();
+        let block_name = slot.block_name();
+        let zfs_vdev = block_name
+            .as_ref()
+            .and_then(|device| owners.zfs_list.find_vdev(device));
+        let zfs_failed = zfs_vdev
+            .and_then(|(_, vdev)| vdev.state.as_ref())
+            .is_some_and(|state| state.is_failed());
+        let zfs_scan = zfs_vdev.and_then(|(pool, vdev)| pool.scan_of(vdev));
+        let md_member = block_name
+            .as_ref()
+            .and_then(|device| owners.md_list.find_member(device));
+        let md_failed = md_member.is_some_and(|(_, member)| member.role == MdRole::Faulty);
+        let md_sync = md_member.and_then(|(array, member)| array.sync_of(member));
+        let lvm_pv = block_name
+            .as_ref()
+            .and_then(|device| owners.lvm_list.find_pv(device));
+        let btrfs_device = block_name
+            .as_ref()
+            .and_then(|device| owners.btrfs_list.find_device(device));
+        let device_flag =
+            if indicators.fault || status.is_some_and(|s| s.is_failed()) || zfs_failed || md_failed
+            {
+                Some(ALERT_FAULT)
+            } else if indicators.locate {
+                Some(ALERT_LOCATING)
+            } else if zfs_scan.is_some() || md_sync.is_some() {
+                Some(ALERT_SCANNING)
+            } else if status.is_some_and(|s| s.is_predicted_failure()) {
+                Some(ALERT_PREDICTED_FAILURE)
+            } else {
+                None
+            };
 
-        let prefix = Some(format!("{} ", slot_id.to_string()));
+        let label = labels.and_then(|labels| labels.get(&slot_id));
+        let prefix = match label {
+            Some(label) => Some(format!("{} ", label)),
+            None => Some(format!("{} ", slot_id)),
+        };
 
+        // the whole holder chain is a line, the label only has its top layer
+        let mut holder_chain = None;
         let mut slot_state;
-        if let Some(device) = slot.block_name() {
+        if let Some(device) = block_name {
             slot_state = SlotState::Device(
                 "__".to_string(),
                 SlotLabel {
@@ -67,18 +758,55 @@ fn load_enclosure(
                 },
                 Vec::new(),
             );
-            'outer: for pool in &zfs_list.pools {
-                for vdev in &pool.vdevs {
-                    if vdev.vdev_name == device {
-                        let key = format!("ZFS {}", pool.pool_name);
-                        if let SlotState::Device(group_key, SlotLabel { content_start, .. }, _) =
-                            &mut slot_state
-                        {
-                            *group_key = key.clone();
-                            *content_start = key;
-                        }
-                        break 'outer;
-                    }
+            if let Some((pool, vdev)) = zfs_vdev {
+                let key = zfs_group_key(pool);
+                let label = match pool.role_tag(vdev) {
+                    Some(tag) => format!("{} [{}]", key, tag),
+                    None => key.clone(),
+                };
+                // idle and in use spares get their own colors
+                let key = if pool.spare(&vdev.vdev_name).is_some() {
+                    label.clone()
+                } else {
+                    key
+                };
+                if let SlotState::Device(group_key, SlotLabel { content_start, .. }, _) =
+                    &mut slot_state
+                {
+                    *group_key = key;
+                    *content_start = label;
+                }
+            } else if let Some((array, member)) = md_member {
+                if let SlotState::Device(group_key, SlotLabel { content_start, .. }, _) =
+                    &mut slot_state
+                {
+                    *group_key = format!("MD {}", array.name);
+                    *content_start = format!("MD {} [{}]", array.name, member.role.name());
+                }
+            } else if let Some(vg_name) = lvm_pv.and_then(|pv| pv.vg_name.as_ref()) {
+                if let SlotState::Device(group_key, SlotLabel { content_start, .. }, _) =
+                    &mut slot_state
+                {
+                    *group_key = format!("LVM {}", vg_name);
+                    *content_start = group_key.clone();
+                }
+            } else if let Some((filesystem, _)) = btrfs_device {
+                if let SlotState::Device(group_key, SlotLabel { content_start, .. }, _) =
+                    &mut slot_state
+                {
+                    *group_key = format!("BTRFS {}", filesystem.name());
+                    *content_start = group_key.clone();
+                }
+            } else {
+                let consumer = owners.holders.consumer_tree(&device);
+                if let (
+                    Some(key),
+                    SlotState::Device(group_key, SlotLabel { content_start, .. }, _),
+                ) = (consumer.group_key(), &mut slot_state)
+                {
+                    *group_key = key.clone();
+                    *content_start = key;
+                    holder_chain = consumer.describe();
                 }
             }
         } else {
@@ -93,135 +821,153 @@ fn load_enclosure(
             )
         }
 
-        // TODO: selectively enable flags
-        let is_expanded = false;
-
         let wwid = if let SlotState::Device(_, _, _) = slot_state {
             slot.device_wwid()
         } else {
             None
         };
-        if is_expanded {
-            let line = wwid.clone().unwrap_or(not_found("no_wwid"));
-            slot_state.lines_mut().push(SlotLine { line });
-        }
-
-        if is_expanded {
-            let line = wwid
-                .unwrap_or(not_found("no_wid_file"))
-                .replace("naa.", "wwn-0x");
-            slot_state.lines_mut().push(SlotLine { line });
-        }
-
-        // Always ATA
-        // if is_wwid {
-        //     let line = slot.device_vendor().unwrap_or(not_found("no_vendor_file"));
-        //     slot_state.lines_mut().push(SlotLine { line });
-        // }
+        let bytes: Option<usize> = slot
+            .block_name()
+            .and_then(|block_name| lsblk_list.iter().find(|v| v.device == block_name))
+            .map(|entry| entry.bytes.parse().unwrap());
+        total_enclosure_bytes += bytes.unwrap_or(0);
 
-        if is_expanded {
-            let line = slot.device_model().unwrap_or(not_found("no_model_file"));
-            slot_state.lines_mut().push(SlotLine { line });
-        }
-
-        if is_expanded {
-            let entry = slot
-                .block_name()
-                .map(|block_name| lsblk_list.iter().find(|v| v.device == block_name))
-                .flatten();
-            let line = match entry {
-                Some(entry) => {
-                    let bytes: usize = entry.bytes.parse().unwrap();
-                    total_enclosure_bytes += bytes;
-
-                    let quantity = (bytes / GIGABYTE).to_formatted_string(LOCALE);
-                    format!("{} G", quantity)
+        for field in &args.fields {
+            let mut line = match field {
+                Field::Wwid => wwid.clone().unwrap_or(not_found("no_wwid")),
+                Field::Wwn => wwid
+                    .clone()
+                    .unwrap_or(not_found("no_wid_file"))
+                    .replace("naa.", "wwn-0x"),
+                // Always ATA
+                // Field::Vendor => slot.device_vendor().unwrap_or(not_found("no_vendor_file")),
+                Field::Model => slot.device_model().unwrap_or(not_found("no_model_file")),
+                Field::Status => {
+                    let mut line = status
+                        .map(|s| s.name().to_string())
+                        .unwrap_or(not_found("no_status"));
+                    if indicators.fault {
+                        line.push_str(" fault");
+                    }
+                    line
                 }
-                None => not_found("no_lsblk"),
+                Field::Size => match bytes {
+                    Some(bytes) => {
+                        let quantity = (bytes / GIGABYTE).to_formatted_string(LOCALE);
+                        format!("{} G", quantity)
+                    }
+                    None => not_found("no_lsblk"),
+                },
+                Field::Zfs => match zfs_vdev {
+                    Some((pool, vdev)) => {
+                        let mut path = vec![pool.pool_name.as_str()];
+                        path.extend(vdev.parent.as_deref());
+                        let health = vdev.health();
+                        path.push(&health);
+                        path.join(" / ")
+                    }
+                    None => not_found("no_zfs"),
+                },
             };
+            // For screenshots, don't leak exact WWNs and Models
+            if args.privacy && matches!(field, Field::Wwid | Field::Wwn | Field::Model) {
+                const BLANKING: usize = 8;
+                let line_len = line.len();
+                let blanking = BLANKING.min(line_len);
+                line.replace_range((line_len - blanking)..(line_len), &"0".repeat(blanking));
+            }
             slot_state.lines_mut().push(SlotLine { line });
-            // todo: this is some percent off???
-            // let line;
-            // if let Some(bytes_str) = slot.block_size() {
-            //     let bytes: usize = bytes_str.parse().expect(&bytes_str);
-            //     // 512 even on 4kn drives
-            //     let disk_block_size: usize = 512;
-            //     const GIGABYTE: usize = 1024usize.pow(3);
-            //     let quantity = (bytes * disk_block_size / GIGABYTE).to_formatted_string(LOCALE);
-            //     line = format!("{} G", quantity)
-            // } else {
-            //     line = "no_size_file".into();
-            // }
         }
+        // not a field, a rebuilding disk should stand out
+        if let Some(scan) = zfs_scan {
+            slot_state.lines_mut().push(SlotLine {
+                line: scan.describe(),
+            });
+        }
+        if let Some(sync) = md_sync {
+            slot_state.lines_mut().push(SlotLine {
+                line: sync.describe(),
+            });
+        }
+        if let Some(chain) = holder_chain {
+            slot_state.lines_mut().push(SlotLine { line: chain });
+        }
+        if let Some(errors) = btrfs_device.and_then(|(_, device)| device.errors) {
+            slot_state.lines_mut().push(SlotLine {
+                line: format!("btrfs {} errors", errors),
+            });
+        }
+        // todo: this is some percent off???
+        // let line;
+        // if let Some(bytes_str) = slot.block_size() {
+        //     let bytes: usize = bytes_str.parse().expect(&bytes_str);
+        //     // 512 even on 4kn drives
+        //     let disk_block_size: usize = 512;
+        //     const GIGABYTE: usize = 1024usize.pow(3);
+        //     let quantity = (bytes * disk_block_size / GIGABYTE).to_formatted_string(LOCALE);
+        //     line = format!("{} G", quantity)
+        // } else {
+        //     line = "no_size_file".into();
+        // }
 
         states.push(slot_state);
     }
 
-    // For screenshots, don't leak exact WWNs, Models, and pool names
-    let privacy_mode = false;
-    if privacy_mode {
-        for slot_state in &mut states {
-            for line in slot_state.lines_mut() {
-                if line.line.ends_with(" G") {
-                    continue;
-                }
+    Ok((states, total_enclosure_bytes))
+}
 
-                const BLANKING: usize = 8;
-                let line_len = line.line.len();
-                let blanking = BLANKING.min(line_len);
-                line.line
-                    .replace_range((line_len - blanking)..(line_len), &"0".repeat(blanking));
-                println!("{}", line.line);
-            }
-        }
+#[cfg(test)]
+mod test {
+    use clap::{error::ErrorKind, CommandFactory};
 
-        let mut bad_pools: Vec<String> = Vec::new();
-        for state in &mut states {
-            let bad_name = &mut state.label_mut().content_start;
-            if !bad_name.starts_with("ZFS") {
-                continue;
-            }
-            let safe_pos = match bad_pools.iter().position(|p| p == bad_name) {
-                Some(p) => p,
-                None => {
-                    bad_pools.push(bad_name.clone());
-                    bad_pools.len() - 1
-                }
-            };
-            *bad_name = format!("ZFS pool{}", safe_pos);
-        }
+    use super::{Cli, Command};
 
-        let mut labels_to_edit: Vec<&SlotLabel> = states.iter().map(|e| e.label()).collect();
-        labels_to_edit.sort_by_key(|l| &l.content_start);
-        labels_to_edit.dedup();
+    fn parse(args: &[&str]) -> Result<Cli, clap::Error> {
+        Cli::try_parse_checked(std::iter::once("viewer").chain(args.iter().copied()))
     }
 
-    let title = format!(
-        "{} {} - {}",
-        enclosure.device_vendor().unwrap_or(not_found("no_vendor")),
-        enclosure.device_model().unwrap_or(not_found("no_model")),
-        enclosure.enc_id()
-    );
-
-    ConsoleViewer {
-        title: Some(title),
-        width,
-        slot_order: SlotPrintOrder::BottomLeftGoingUp,
+    #[test]
+    fn test_cli() {
+        Cli::command().debug_assert();
     }
-    .print(&states);
 
-    println!(
-        "Total Size {} G",
-        (total_enclosure_bytes / GIGABYTE).to_formatted_string(LOCALE)
-    )
-}
+    #[test]
+    fn test_global_flags() {
+        let global = ["--sysfs", "/host/sys", "--enclosure", "0:0:1:0"];
+        let subcommands: [&[&str]; 6] = [
+            &[],
+            &["show", "--width", "6"],
+            &["locate", "sdb", "off"],
+            &["fault", "sdb"],
+            &["find", "sdb"],
+            &["export"],
+        ];
+        for subcommand in subcommands {
+            let before = [&global[..], subcommand].concat();
+            let after = [subcommand, &global[..]].concat();
+            for args in [before, after] {
+                let cli = parse(&args).unwrap_or_else(|err| panic!("{args:?} {err}"));
+                assert_eq!(cli.sysfs.to_str(), Some("/host/sys"), "{args:?}");
+                assert_eq!(cli.enclosures, ["0:0:1:0"], "{args:?}");
+            }
+        }
 
-const ENABLE_NOT_FOUND: bool = false;
+        let cli = parse(&["--width", "6"]).unwrap();
+        assert!(cli.command.is_none());
+        assert_eq!(cli.show.width, Some(6));
+        let cli = parse(&["--sysfs", "/host/sys", "show", "--width", "6"]).unwrap();
+        assert!(matches!(cli.command, Some(Command::Show(show)) if show.width == Some(6)));
 
-fn not_found(msg: &str) -> String {
-    if ENABLE_NOT_FOUND {
-        msg.into()
-    } else {
-        "".into()
+        let err = parse(&["--width", "6", "locate", "sdb"]).err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::ArgumentConflict);
+    }
+
+    #[test]
+    fn test_locate_for_state() {
+        assert!(parse(&["locate", "sdb", "--for", "2s"]).is_ok());
+        let err = parse(&["locate", "sdb", "off", "--for", "2s"])
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), ErrorKind::ArgumentConflict);
     }
 }
@@ -0,0 +1,142 @@
+use std::{
+    fs::{canonicalize, read_dir},
+    path::{Path, PathBuf},
+};
+
+use crate::sysfs::SysfsRoot;
+
+/// Maps what ZFS, md or LVM call a device back to the whole disks in the slots
+pub struct BlockResolver {
+    sysfs: SysfsRoot,
+    dev: PathBuf,
+}
+
+impl BlockResolver {
+    pub fn new(sysfs: SysfsRoot, dev: impl Into<PathBuf>) -> Self {
+        Self {
+            sysfs,
+            dev: dev.into(),
+        }
+    }
+
+    /// `/dev` next to the sysfs root
+    pub fn from_sysfs(sysfs: &SysfsRoot) -> Self {
+        Self::new(sysfs.clone(), sysfs.dev_dir())
+    }
+
+    /// `sda1`, `/dev/disk/by-id/wwn-0x5000c500a1b2c3d4-part1`, `/dev/mapper/mpatha` or `dm-3`.
+    /// Names sysfs doesn't know are returned as is
+    pub fn whole_disks(&self, name: &str) -> Vec<String> {
+        let mut disks = Vec::new();
+        self.collect_disks(&self.kernel_name(name), &mut disks);
+        disks
+    }
+
+    /// `dm-3` for `/dev/mapper/mpatha`, names outside `/dev` are returned as is
+    pub fn kernel_name(&self, name: &str) -> String {
+        match name.strip_prefix("/dev/") {
+            Some(dev_path) => {
+                let path = self.dev.join(dev_path);
+                // by-id, by-path and mapper names are symlinks to the kernel name
+                let path = canonicalize(&path).unwrap_or(path);
+                file_name(&path).unwrap_or(dev_path.to_string())
+            }
+            None => name.to_string(),
+        }
+    }
+
+    fn collect_disks(&self, name: &str, disks: &mut Vec<String>) {
+        let class_path = self.sysfs.block_dir().join(name);
+        if !class_path.exists() {
+            push_unique(disks, name);
+            return;
+        }
+
+        // partitions are under their disk, devices/.../block/sda/sda1
+        if class_path.join("partition").exists() {
+            let parent = canonicalize(&class_path)
+                .ok()
+                .and_then(|path| path.parent().and_then(file_name));
+            match parent {
+                Some(parent) => self.collect_disks(&parent, disks),
+                None => push_unique(disks, name),
+            }
+            return;
+        }
+
+        // dm and md devices are built on top of slaves
+        let slaves = self.slaves(&class_path);
+        if slaves.is_empty() {
+            push_unique(disks, name);
+        }
+        for slave in slaves {
+            self.collect_disks(&slave, disks);
+        }
+    }
+
+    fn slaves(&self, class_path: &Path) -> Vec<String> {
+        let Ok(entries) = read_dir(class_path.join("slaves")) else {
+            return Vec::new();
+        };
+        let mut slaves: Vec<String> = entries
+            .flatten()
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .collect();
+        slaves.sort();
+        slaves
+    }
+}
+
+/// `name` as ZFS, md, LVM or btrfs call a member, `disks` from [BlockResolver::whole_disks]
+pub fn is_on_disk(name: &str, disks: &[String], disk: &str) -> bool {
+    name.strip_prefix("/dev/").unwrap_or(name) == disk || disks.iter().any(|d| d == disk)
+}
+
+fn file_name(path: &Path) -> Option<String> {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+}
+
+fn push_unique(disks: &mut Vec<String>, name: &str) {
+    if !disks.iter().any(|disk| disk == name) {
+        disks.push(name.to_string());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::BlockResolver;
+    use crate::sysfs::fixture::SysfsFixture;
+
+    #[test]
+    fn test_whole_disks() {
+        let fixture = SysfsFixture::new("blockdev");
+        fixture
+            .dir("devices/host0/block/sdb/sdb1")
+            .file("devices/host0/block/sdb/sdb1/partition", "1")
+            .dir("devices/host0/block/sdc")
+            .dir("devices/host0/block/sdd")
+            .dir("devices/virtual/block/dm-3/slaves/sdc")
+            .dir("devices/virtual/block/dm-3/slaves/sdd")
+            .symlink("class/block/sdb", "../../devices/host0/block/sdb")
+            .symlink("class/block/sdb1", "../../devices/host0/block/sdb/sdb1")
+            .symlink("class/block/sdc", "../../devices/host0/block/sdc")
+            .symlink("class/block/sdd", "../../devices/host0/block/sdd")
+            .symlink("class/block/dm-3", "../../devices/virtual/block/dm-3")
+            .host_file("dev/sdb1", "")
+            .host_file("dev/dm-3", "")
+            .host_symlink("dev/disk/by-id/wwn-0x5000c500a1b2c3d4-part1", "../../sdb1")
+            .host_symlink("dev/mapper/mpatha", "../dm-3");
+        let resolver = BlockResolver::from_sysfs(&fixture.sysfs());
+
+        assert_eq!(resolver.whole_disks("sdb"), ["sdb"]);
+        assert_eq!(resolver.whole_disks("sdb1"), ["sdb"]);
+        assert_eq!(
+            resolver.whole_disks("/dev/disk/by-id/wwn-0x5000c500a1b2c3d4-part1"),
+            ["sdb"]
+        );
+        assert_eq!(resolver.whole_disks("/dev/mapper/mpatha"), ["sdc", "sdd"]);
+        assert_eq!(resolver.whole_disks("dm-3"), ["sdc", "sdd"]);
+        assert_eq!(resolver.whole_disks("nvme9n1"), ["nvme9n1"]);
+    }
+}
@@ -0,0 +1,203 @@
+use std::{fs::read_dir, io::ErrorKind};
+
+use crate::{
+    blockdev::{is_on_disk, BlockResolver},
+    err::{io_op, SResult},
+    sysfs::SysfsRoot,
+    utils::{read_to_string_trim, try_execute_command},
+};
+
+/// Mounted btrfs filesystems and the devices under them
+#[derive(Debug, Default, PartialEq)]
+pub struct BtrfsList {
+    pub filesystems: Vec<BtrfsFilesystem>,
+}
+
+impl BtrfsList {
+    /// Empty without btrfs mounted
+    pub fn load(sysfs: &SysfsRoot, resolver: &BlockResolver) -> SResult<Self> {
+        let mut btrfs_list = Self::read_filesystems(sysfs)?;
+        btrfs_list.resolve_disks(resolver);
+        for filesystem in &mut btrfs_list.filesystems {
+            // any member names the whole filesystem
+            let Some(first) = filesystem.devices.first() else {
+                continue;
+            };
+            let dev_path = format!("/dev/{}", first.device);
+            match try_execute_command("btrfs", ["device", "stats", &dev_path]) {
+                Ok(res) => filesystem.set_device_stats(&res, resolver),
+                Err(err) => println!("[E] {}", err),
+            }
+        }
+        Ok(btrfs_list)
+    }
+
+    /// `fs/btrfs/<uuid>/devices/<name>` and `fs/btrfs/<uuid>/label`
+    fn read_filesystems(sysfs: &SysfsRoot) -> SResult<Self> {
+        let btrfs_dir = sysfs.btrfs_dir();
+        let entries = match read_dir(&btrfs_dir) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(Self::default()),
+            Err(err) => return io_op(Err(err), &btrfs_dir),
+        };
+        let mut filesystems = Vec::new();
+        for entry in entries {
+            let fs_dir = io_op(entry, &btrfs_dir)?.path();
+            let devices_dir = fs_dir.join("devices");
+            // features/ is next to the uuids
+            let Ok(device_entries) = read_dir(&devices_dir) else {
+                continue;
+            };
+            let mut devices: Vec<BtrfsDevice> = device_entries
+                .map(|entry| {
+                    io_op(entry, &devices_dir).map(|entry| BtrfsDevice {
+                        device: entry.file_name().to_string_lossy().to_string(),
+                        disks: Vec::new(),
+                        errors: None,
+                    })
+                })
+                .collect::<SResult<_>>()?;
+            devices.sort_by(|a, b| a.device.cmp(&b.device));
+            filesystems.push(BtrfsFilesystem {
+                uuid: fs_dir
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_default(),
+                label: read_to_string_trim(fs_dir.join("label"))
+                    .ok()
+                    .filter(|label| !label.is_empty()),
+                devices,
+            });
+        }
+        filesystems.sort_by(|a, b| a.uuid.cmp(&b.uuid));
+        Ok(Self { filesystems })
+    }
+
+    fn resolve_disks(&mut self, resolver: &BlockResolver) {
+        for filesystem in &mut self.filesystems {
+            for device in &mut filesystem.devices {
+                device.disks = resolver.whole_disks(&device.device);
+            }
+        }
+    }
+
+    /// By member name or the whole disk under it
+    pub fn find_device(&self, device: &str) -> Option<(&BtrfsFilesystem, &BtrfsDevice)> {
+        self.filesystems.iter().find_map(|filesystem| {
+            filesystem
+                .devices
+                .iter()
+                .find(|member| is_on_disk(&member.device, &member.disks, device))
+                .map(|member| (filesystem, member))
+        })
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct BtrfsFilesystem {
+    pub uuid: String,
+    pub label: Option<String>,
+    pub devices: Vec<BtrfsDevice>,
+}
+
+impl BtrfsFilesystem {
+    /// Label, or the start of the uuid like `btrfs filesystem show`
+    pub fn name(&self) -> &str {
+        match &self.label {
+            Some(label) => label,
+            None => self.uuid.get(..8).unwrap_or(&self.uuid),
+        }
+    }
+
+    /// `[/dev/sdb].write_io_errs    0` for every counter of every device, summed per device
+    fn set_device_stats(&mut self, res: &str, resolver: &BlockResolver) {
+        for line in res.lines() {
+            let Some((dev_path, rest)) = line
+                .trim()
+                .strip_prefix('[')
+                .and_then(|l| l.split_once(']'))
+            else {
+                continue;
+            };
+            let Some(count) = rest
+                .split_whitespace()
+                .last()
+                .and_then(|count| count.parse::<u64>().ok())
+            else {
+                continue;
+            };
+            // stats name /dev/mapper/... where sysfs has dm-0
+            let disks = resolver.whole_disks(dev_path);
+            if let Some(device) = self.devices.iter_mut().find(|device| device.disks == disks) {
+                *device.errors.get_or_insert(0) += count;
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct BtrfsDevice {
+    /// Kernel name, `sdb`, `sdb1` or `dm-0`
+    pub device: String,
+    /// Whole disks under a partition, dm-crypt or multipath device
+    pub disks: Vec<String>,
+    /// All `btrfs device stats` counters, when the command worked
+    pub errors: Option<u64>,
+}
+
+#[cfg(test)]
+mod test {
+    use super::BtrfsList;
+    use crate::{blockdev::BlockResolver, sysfs::fixture::SysfsFixture};
+
+    #[test]
+    fn test_filesystems() {
+        let fixture = SysfsFixture::new("btrfs");
+        fixture
+            .file("devices/host0/block/sdc/sdc1/partition", "1")
+            .symlink("class/block/sdc1", "../../devices/host0/block/sdc/sdc1")
+            .file("fs/btrfs/features/raid1c34", "0")
+            .file(
+                "fs/btrfs/4a7f2c9e-8d1b-4c3e-9f0a-1b2c3d4e5f60/label",
+                "media\n",
+            )
+            .symlink(
+                "fs/btrfs/4a7f2c9e-8d1b-4c3e-9f0a-1b2c3d4e5f60/devices/sdb",
+                "../../../../devices/host0/block/sdb",
+            )
+            .symlink(
+                "fs/btrfs/4a7f2c9e-8d1b-4c3e-9f0a-1b2c3d4e5f60/devices/sdc1",
+                "../../../../devices/host0/block/sdc/sdc1",
+            )
+            .file("fs/btrfs/9e8d7c6b-0000-4c3e-9f0a-1b2c3d4e5f60/label", "\n")
+            .dir("fs/btrfs/9e8d7c6b-0000-4c3e-9f0a-1b2c3d4e5f60/devices/sdd");
+        let resolver = BlockResolver::from_sysfs(&fixture.sysfs());
+
+        let mut btrfs_list = BtrfsList::read_filesystems(&fixture.sysfs()).unwrap();
+        assert_eq!(btrfs_list.filesystems.len(), 2);
+        btrfs_list.resolve_disks(&resolver);
+        btrfs_list.filesystems[0].set_device_stats(
+            "[/dev/sdb].write_io_errs    0
+[/dev/sdb].read_io_errs     0
+[/dev/sdb].flush_io_errs    0
+[/dev/sdb].corruption_errs  0
+[/dev/sdb].generation_errs  0
+[/dev/sdc1].write_io_errs    2
+[/dev/sdc1].read_io_errs     0
+[/dev/sdc1].flush_io_errs    0
+[/dev/sdc1].corruption_errs  5
+[/dev/sdc1].generation_errs  0",
+            &resolver,
+        );
+
+        let (media, sdc1) = btrfs_list.find_device("sdc").unwrap();
+        assert_eq!(media.name(), "media");
+        assert_eq!(sdc1.device, "sdc1");
+        assert_eq!(sdc1.errors, Some(7));
+        assert_eq!(btrfs_list.find_device("sdb").unwrap().1.errors, Some(0));
+
+        let (unlabeled, sdd) = btrfs_list.find_device("sdd").unwrap();
+        assert_eq!(unlabeled.name(), "9e8d7c6b");
+        assert_eq!(sdd.errors, None);
+    }
+}
@@ -22,8 +22,9 @@ pub struct ColorWheel {
 }
 
 impl ColorWheel {
-    pub fn next(&mut self) -> &'static str {
-        let color = BACKGROUND_COLORS[self.i];
+    /// Starts over after the last color, more groups than colors share them
+    pub fn next_color(&mut self) -> &'static str {
+        let color = BACKGROUND_COLORS[self.i % BACKGROUND_COLORS.len()];
         self.i += 1;
         color
     }
@@ -40,7 +41,7 @@ impl<T: Eq + Hash + ToString> ColorMap<T> {
         // let dc_entry = data_entry.to_string();
         self.data.entry(data_entry).or_insert_with(|| {
             // println!("inserting {}", dc_entry);
-            self.color_wheel.next()
+            self.color_wheel.next_color()
         })
     }
 }
@@ -1,31 +1,67 @@
-use std::borrow::Borrow;
+use std::{borrow::Borrow, str::FromStr};
 
-use crate::colors::{ColorMap, ASCII_RESET};
+use crate::{
+    colors::{ColorMap, ASCII_RESET},
+    err::SResult,
+    layout::{PanelLayout, PanelView},
+};
 
 pub struct ConsoleViewer {
-    pub width: usize,
     pub title: Option<String>,
-    pub slot_order: SlotPrintOrder,
+    /// Front first then rear
+    pub panels: Vec<PanelLayout>,
+    pub legend: Option<Legend>,
+}
+
+/// Table under the grid, each row starts with a swatch in its group's colour
+pub struct Legend {
+    pub header: Vec<String>,
+    pub rows: Vec<LegendRow>,
+}
+
+pub struct LegendRow {
+    /// Same key as [`SlotState::Device`]
+    pub group_key: String,
+    pub cells: Vec<String>,
 }
 
 const U_FULL_BLOCK: &str = "\u{2588}";
 const U_LOWER_ONE_EIGHTH_BLOCK: &str = "\u{2581}";
 const U_LEFT_ONE_EIGHTH_BLOCK: &str = "\u{258F}";
+const U_LIGHT_SHADE: &str = "\u{2591}";
 
 pub const ALERT_LOCATING: &str = "🚨";
+/// Resilver or scrub running on the disk
+pub const ALERT_SCANNING: &str = "🔄";
+pub const ALERT_FAULT: &str = "⛔";
+pub const ALERT_PREDICTED_FAILURE: &str = "🔶";
+
+const DRAWER_ROW_LABEL_WIDTH: usize = 3;
+const VERTICAL_CELL_WIDTH: usize = 3;
+const DRAWER_EMPTY: &str = "-";
 
 const PADDING_PREFIX: usize = 3;
 const PADDING_SUFFIX: usize = 2;
 
-impl ConsoleViewer {
-    pub fn print(&self, states: &[SlotState]) {
+/// Every cell is sized for the biggest slot so panels line up
+struct CellSize {
+    start_width: usize,
+    end_width: usize,
+    prefix_width: usize,
+    lines: usize,
+}
+
+impl CellSize {
+    fn measure<'s>(states: impl Iterator<Item = &'s SlotState>) -> Self {
         let mut cell_start_width = 0;
         let mut cell_end_width = 0;
         let mut cell_lines = 0;
+        let mut prefix_width = PADDING_PREFIX;
         for state in states {
             let SlotLabel {
                 content_start,
                 content_end,
+                prefix,
                 ..
             } = state.label();
 
@@ -40,18 +76,71 @@ impl ConsoleViewer {
                 .max(content_start.len())
                 .max(lines_width_max);
             cell_end_width = cell_end_width.max(content_end.len());
-            cell_lines = state.lines().len();
+            // resilvering disks have an extra line
+            cell_lines = cell_lines.max(state.lines().len());
+            // layout labels can be longer than slot numbers
+            prefix_width = prefix_width.max(prefix.as_ref().map(|p| p.len()).unwrap_or(0));
         }
         // anti squish
         cell_start_width += 1;
         cell_end_width += 1;
-        let cell_content_width = cell_start_width + cell_end_width;
+        Self {
+            start_width: cell_start_width,
+            end_width: cell_end_width,
+            prefix_width,
+            lines: cell_lines,
+        }
+    }
+
+    fn content_width(&self) -> usize {
+        self.start_width + self.end_width
+    }
 
-        let row_sep = U_LOWER_ONE_EIGHTH_BLOCK
-            .repeat(cell_content_width + 1 /*cols*/ + PADDING_PREFIX + PADDING_SUFFIX)
-            .repeat(self.width);
-        let row_char_len = row_sep.chars().count();
-        let column_sep = U_LEFT_ONE_EIGHTH_BLOCK;
+    fn width(&self) -> usize {
+        self.prefix_width + self.content_width() + PADDING_SUFFIX
+    }
+}
+
+impl ConsoleViewer {
+    /// Slots fill the panels in order, the last panel takes any that don't fit
+    pub fn print<'s>(&'s self, states: &'s [SlotState]) {
+        let mut panel_states = Vec::with_capacity(self.panels.len());
+        let mut rest = states;
+        for count in fill_counts(&self.panels.iter().collect::<Vec<_>>(), states.len()) {
+            let (head, tail) = rest.split_at(count);
+            panel_states.push(head);
+            rest = tail;
+        }
+        self.print_panels(&panel_states);
+    }
+
+    /// Slots for each panel, eg when the rear backplane is its own enclosure
+    pub fn print_panels<'s>(&'s self, panel_states: &[&'s [SlotState]]) {
+        println!("{}", self.render_panels(panel_states));
+    }
+
+    fn render_panels<'s>(&'s self, panel_states: &[&'s [SlotState]]) -> String {
+        let cell = CellSize::measure(panel_states.iter().flat_map(|states| states.iter()));
+        // drawers of one chassis share columns too
+        let drawer_width = drawer_cell_width(
+            self.panels
+                .iter()
+                .zip(panel_states)
+                .filter(|(panel, _)| panel.view == PanelView::Drawer)
+                .flat_map(|(_, states)| states.iter()),
+        );
+        let row_char_len = self
+            .panels
+            .iter()
+            .map(|panel| match panel.view {
+                PanelView::Bays => (cell.width() + 1) * panel.widest_row(),
+                PanelView::Drawer => {
+                    DRAWER_ROW_LABEL_WIDTH + (drawer_width + 1) * panel.widest_row() + 1
+                }
+                PanelView::Vertical => (VERTICAL_CELL_WIDTH + 1) * panel.widest_row(),
+            })
+            .max()
+            .unwrap_or(0);
 
         let mut pool_colors = ColorMap::default();
 
@@ -60,82 +149,449 @@ impl ConsoleViewer {
         output.push_str(&U_FULL_BLOCK.repeat(row_char_len));
         output.push('\n');
         if let Some(title) = &self.title {
-            output.push_str(&format!("{:-^row_char_len$}", title,));
+            let lines = wrap(title, row_char_len);
+            output.push_str(
+                &lines
+                    .iter()
+                    .map(|line| format!("{:-^row_char_len$}", line))
+                    .collect::<Vec<String>>()
+                    .join("\n"),
+            );
         }
 
-        let mut slot_line_buffer: Vec<String> = vec!["".to_string(); cell_lines];
+        for (panel, states) in self.panels.iter().zip(panel_states) {
+            if panel.view != PanelView::Drawer && self.panels.len() > 1 {
+                for line in wrap(&panel.name.to_uppercase(), row_char_len) {
+                    output.push('\n');
+                    output.push_str(&format!("{:^row_char_len$}", line));
+                }
+            }
+            match panel.view {
+                PanelView::Bays => print_panel(panel, states, &cell, &mut pool_colors, &mut output),
+                PanelView::Vertical => print_vertical(panel, states, &mut pool_colors, &mut output),
+                PanelView::Drawer => {
+                    let heading = format!("{} - row A at the front", panel.name.to_uppercase());
+                    for line in wrap(&heading, row_char_len) {
+                        output.push('\n');
+                        output.push_str(&format!("{:^row_char_len$}", line));
+                    }
+                    print_drawer(panel, states, drawer_width, &mut pool_colors, &mut output);
+                }
+            }
+        }
+        if let Some(legend) = &self.legend {
+            print_legend(legend, &mut pool_colors, &mut output);
+        }
+        output
+    }
+}
+
+/// Slots for each panel. The chassis's own slots fill its panels in order, the last of them
+/// taking any that don't fit. A panel backed by another enclosure gets that enclosure's slots
+/// from `enclosure_states`, or none when it isn't there
+pub fn split_panels<T>(
+    panels: &[PanelLayout],
+    states: Vec<T>,
+    mut enclosure_states: impl FnMut(&PanelLayout, &str) -> SResult<Option<Vec<T>>>,
+) -> SResult<Vec<Vec<T>>> {
+    let own_panels: Vec<&PanelLayout> = panels
+        .iter()
+        .filter(|panel| panel.enclosure.is_none())
+        .collect();
+    let mut own_counts = fill_counts(&own_panels, states.len()).into_iter();
+    let mut own_states = states.into_iter();
+    let mut panel_states = Vec::with_capacity(panels.len());
+    for panel in panels {
+        match &panel.enclosure {
+            Some(id) => panel_states.push(enclosure_states(panel, id)?.unwrap_or_else(|| {
+                println!("[E] {} panel enclosure {} not found", panel.name, id);
+                Vec::new()
+            })),
+            None => {
+                let count = own_counts.next().unwrap_or(0);
+                panel_states.push(own_states.by_ref().take(count).collect());
+            }
+        }
+    }
+    Ok(panel_states)
+}
+
+/// Slots in each panel up to its capacity, the last panel takes the rest
+fn fill_counts(panels: &[&PanelLayout], total: usize) -> Vec<usize> {
+    let mut rest = total;
+    panels
+        .iter()
+        .enumerate()
+        .map(|(i, panel)| {
+            let count = if i + 1 == panels.len() {
+                rest
+            } else {
+                panel.capacity().min(rest)
+            };
+            rest -= count;
+            count
+        })
+        .collect()
+}
+
+/// Lines of whole words no wider than `width`, narrow grids can't fit the title on one
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        let mut word: Vec<char> = word.chars().collect();
+        if !line.is_empty() && line.chars().count() + 1 + word.len() > width {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        // longer than a whole line, eg a list of enclosure paths without spaces
+        while line.chars().count() + word.len() > width.max(1) {
+            let split = width.max(1) - line.chars().count();
+            line.extend(word.drain(..split));
+            lines.push(std::mem::take(&mut line));
+        }
+        line.extend(word);
+    }
+    if !line.is_empty() || lines.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
 
-        for (i, slot) in self
-            .slot_order
-            .order(states.len(), self.width)
+/// Columns padded to the widest cell, colours continue from the grid
+fn print_legend<'s>(legend: &'s Legend, pool_colors: &mut ColorMap<&'s str>, output: &mut String) {
+    if legend.rows.is_empty() {
+        return;
+    }
+    let mut widths: Vec<usize> = legend.header.iter().map(|cell| cell.len()).collect();
+    for row in &legend.rows {
+        widths.resize(widths.len().max(row.cells.len()), 0);
+        for (width, cell) in widths.iter_mut().zip(&row.cells) {
+            *width = (*width).max(cell.len());
+        }
+    }
+    let columns = |cells: &[String]| {
+        cells
             .iter()
-            .enumerate()
-        {
-            let slot = &states[*slot];
+            .zip(&widths)
+            .map(|(cell, width)| format!("{:<width$}", cell))
+            .collect::<Vec<String>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+    };
 
-            if i % self.width == 0 {
-                if i != 0 {
-                    output.push_str(&column_sep);
-                }
-                output.push('\n');
+    output.push('\n');
+    output.push_str(&format!("   {}", columns(&legend.header)));
+    for row in &legend.rows {
+        output.push('\n');
+        output.push_str(&format!(
+            "{}{}{} {}",
+            pool_colors.get_color(row.group_key.as_str()),
+            U_FULL_BLOCK.repeat(2),
+            ASCII_RESET,
+            columns(&row.cells)
+        ));
+    }
+}
 
-                if i != 0 {
-                    append_lines(slot_line_buffer, &mut output, column_sep);
-                }
+fn print_panel<'s>(
+    panel: &PanelLayout,
+    states: &'s [SlotState],
+    cell: &CellSize,
+    pool_colors: &mut ColorMap<&'s str>,
+    output: &mut String,
+) {
+    let cell_start_width = cell.start_width;
+    let cell_end_width = cell.end_width;
+    let cell_content_width = cell.content_width();
+    let cell_width = cell.width();
+    let prefix_width = cell.prefix_width;
+    let cell_lines = cell.lines;
 
-                slot_line_buffer = vec!["".to_string(); cell_lines];
+    let grid = panel_grid(panel, states);
+    // short rows leave the rest of the widest row as placeholders
+    let columns = panel.widest_row();
+    let row_sep = U_LOWER_ONE_EIGHTH_BLOCK
+        .repeat(cell_width + 1 /*cols*/)
+        .repeat(columns);
+    let column_sep = U_LEFT_ONE_EIGHTH_BLOCK;
 
-                output.push_str(&row_sep);
-                output.push('\n');
+    let mut slot_line_buffer: Vec<String> = vec!["".to_string(); cell_lines];
+
+    for (i, cell) in grid.iter().enumerate() {
+        if i % columns == 0 {
+            if i != 0 {
+                output.push_str(column_sep);
             }
-            output.push_str(&column_sep);
+            output.push('\n');
 
-            let label_color = if let SlotState::Device(group_key, _, _) = &slot {
-                pool_colors.get_color(group_key.as_str())
-            } else {
-                ""
-            };
+            if i != 0 {
+                append_lines(slot_line_buffer, output, column_sep);
+            }
 
-            let SlotLabel {
-                content_start,
-                content_end,
-                prefix,
-                suffix,
-            } = slot.label();
+            slot_line_buffer = vec!["".to_string(); cell_lines];
 
+            output.push_str(&row_sep);
+            output.push('\n');
+        }
+        output.push_str(column_sep);
+
+        // gap in the chassis for a PSU or fan, or past the end of a short row
+        let Some(slot) = cell else {
+            output.push_str(&U_LIGHT_SHADE.repeat(cell_width));
+            for line in slot_line_buffer.iter_mut() {
+                line.push_str(column_sep);
+                line.push_str(&" ".repeat(cell_width));
+            }
+            continue;
+        };
+        let slot = &states[*slot];
+
+        let label_color = if let SlotState::Device(group_key, _, _) = &slot {
+            pool_colors.get_color(group_key.as_str())
+        } else {
+            ""
+        };
+
+        let SlotLabel {
+            content_start,
+            content_end,
+            prefix,
+            suffix,
+        } = slot.label();
+
+        output.push_str(&format!(
+            "{}{}{:cell_start_width$}{:>cell_end_width$}{}{}",
+            label_color,
+            huge_flag(prefix, prefix_width),
+            content_start,
+            content_end,
+            huge_flag_str(suffix, PADDING_SUFFIX),
+            ASCII_RESET
+        ));
+
+        // wheee
+        for (line_num, line) in slot_line_buffer.iter_mut().enumerate() {
+            line.push_str(column_sep);
+
+            let content = slot
+                .lines()
+                .get(line_num)
+                .map(|line| line.line.as_str())
+                .unwrap_or("");
+
+            line.push_str(&format!(
+                "{}{:prefix_width$}{:<cell_content_width$}{:PADDING_SUFFIX$}{}",
+                label_color, "", content, "", ASCII_RESET
+            ));
+        }
+    }
+    output.push_str(column_sep);
+    output.push('\n');
+    append_lines(slot_line_buffer, output, column_sep);
+
+    output.push_str(&row_sep);
+}
+
+/// One narrow column per slot, each label and line stacked top to bottom
+fn print_vertical<'s>(
+    panel: &PanelLayout,
+    states: &'s [SlotState],
+    pool_colors: &mut ColorMap<&'s str>,
+    output: &mut String,
+) {
+    let grid = panel_grid(panel, states);
+    let columns = panel.widest_row();
+    let row_sep = U_LOWER_ONE_EIGHTH_BLOCK.repeat((VERTICAL_CELL_WIDTH + 1) * columns);
+    let column_sep = U_LEFT_ONE_EIGHTH_BLOCK;
+
+    let sections: Vec<Vec<Vec<char>>> = states.iter().map(vertical_sections).collect();
+    // line up each section across the slots
+    let mut section_heights: Vec<usize> = Vec::new();
+    for slot_sections in &sections {
+        section_heights.resize(section_heights.len().max(slot_sections.len()), 0);
+        for (height, section) in section_heights.iter_mut().zip(slot_sections) {
+            *height = (*height).max(section.len());
+        }
+    }
+
+    for row in grid.chunks(columns) {
+        output.push('\n');
+        output.push_str(&row_sep);
+
+        let label_colors: Vec<&str> = row
+            .iter()
+            .map(|cell| match cell.map(|slot| &states[slot]) {
+                Some(SlotState::Device(group_key, _, _)) => pool_colors.get_color(group_key),
+                _ => "",
+            })
+            .collect();
+
+        // flags first so they line up along the top
+        output.push('\n');
+        for (cell, label_color) in row.iter().zip(&label_colors) {
+            output.push_str(column_sep);
+            let Some(slot) = cell else {
+                output.push_str(&U_LIGHT_SHADE.repeat(VERTICAL_CELL_WIDTH));
+                continue;
+            };
             output.push_str(&format!(
-                "{}{}{:cell_start_width$}{:>cell_end_width$}{}{}",
+                "{}{}{}",
                 label_color,
-                huge_flag(prefix, PADDING_PREFIX),
-                content_start,
-                content_end,
-                huge_flag_str(suffix, PADDING_SUFFIX),
+                huge_flag_str(&states[*slot].label().suffix, VERTICAL_CELL_WIDTH),
                 ASCII_RESET
             ));
+            if states[*slot].label().suffix.is_some() {
+                // emojiis are 2 wide
+                output.push(' ');
+            }
+        }
+        output.push_str(column_sep);
+
+        for (section_num, height) in section_heights.iter().enumerate() {
+            // blank line between sections
+            for char_num in (0..=*height).map(|char_num| char_num.checked_sub(1)) {
+                output.push('\n');
+                for (cell, label_color) in row.iter().zip(&label_colors) {
+                    output.push_str(column_sep);
+                    let Some(slot) = cell else {
+                        output.push_str(&U_LIGHT_SHADE.repeat(VERTICAL_CELL_WIDTH));
+                        continue;
+                    };
+                    let c = char_num
+                        .and_then(|char_num| sections[*slot].get(section_num)?.get(char_num))
+                        .copied()
+                        .unwrap_or(' ');
+                    output.push_str(&format!(
+                        "{}{:^VERTICAL_CELL_WIDTH$}{}",
+                        label_color, c, ASCII_RESET
+                    ));
+                }
+                output.push_str(column_sep);
+            }
+        }
+    }
+    output.push('\n');
+    output.push_str(&row_sep);
+}
 
-            // wheee
-            for line_num in 0..cell_lines {
-                let line = &mut slot_line_buffer[line_num];
-                line.push_str(&column_sep);
+/// Slot number, both label halves, then every line
+fn vertical_sections(state: &SlotState) -> Vec<Vec<char>> {
+    let label = state.label();
+    let mut sections = vec![
+        label
+            .prefix
+            .as_deref()
+            .unwrap_or("")
+            .trim()
+            .chars()
+            .collect(),
+        label.content_start.chars().collect(),
+        label.content_end.chars().collect(),
+    ];
+    sections.extend(state.lines().iter().map(|line| line.line.chars().collect()));
+    sections
+}
+
+/// Top down plan of a drawer, one compact cell per slot without the extra lines
+fn print_drawer<'s>(
+    panel: &PanelLayout,
+    states: &'s [SlotState],
+    cell_width: usize,
+    pool_colors: &mut ColorMap<&'s str>,
+    output: &mut String,
+) {
+    let grid = panel_grid(panel, states);
+    let columns = panel.widest_row();
+    let content_width = cell_width - PADDING_SUFFIX;
+    let column_sep = U_LEFT_ONE_EIGHTH_BLOCK;
 
-                let content = &slot.lines().get(line_num).unwrap().line;
+    output.push('\n');
+    output.push_str(&" ".repeat(DRAWER_ROW_LABEL_WIDTH));
+    for column in 1..=columns {
+        output.push_str(&format!(" {:^cell_width$}", column));
+    }
 
-                line.push_str(&format!(
-                    "{}{:PADDING_PREFIX$}{:<cell_content_width$}{:PADDING_SUFFIX$}{}",
-                    label_color, "", content, "", ASCII_RESET
-                ));
+    for (i, cell) in grid.iter().enumerate() {
+        if i % columns == 0 {
+            if i != 0 {
+                output.push_str(column_sep);
             }
+            output.push('\n');
+            output.push_str(&format!(
+                "{:>width$} ",
+                row_letter(i / columns),
+                width = DRAWER_ROW_LABEL_WIDTH - 1
+            ));
         }
-        output.push_str(&column_sep);
-        output.push('\n');
-        append_lines(slot_line_buffer, &mut output, column_sep);
+        output.push_str(column_sep);
 
-        output.push_str(&row_sep);
+        let Some(slot) = cell else {
+            output.push_str(&U_LIGHT_SHADE.repeat(cell_width));
+            continue;
+        };
+        let slot = &states[*slot];
+        let (label_color, content) = match slot {
+            SlotState::Device(group_key, label, _) => (
+                pool_colors.get_color(group_key.as_str()),
+                label.content_end.as_str(),
+            ),
+            SlotState::Empty(_, _) => ("", DRAWER_EMPTY),
+        };
+        output.push_str(&format!(
+            "{}{:>content_width$}{}{}",
+            label_color,
+            content,
+            huge_flag_str(&slot.label().suffix, PADDING_SUFFIX),
+            ASCII_RESET
+        ));
+    }
+    output.push_str(column_sep);
+}
+
+/// Device name and flag, wide enough for the column numbers
+fn drawer_cell_width<'s>(states: impl Iterator<Item = &'s SlotState>) -> usize {
+    let content_width = states
+        .filter(|state| matches!(state, SlotState::Device(_, _, _)))
+        .map(|state| state.label().content_end.len())
+        .max()
+        .unwrap_or(0)
+        .max(DRAWER_EMPTY.len());
+    // anti squish
+    1 + content_width + PADDING_SUFFIX
+}
 
-        println!("{}", output);
+/// A, B, ... Z, then row numbers
+fn row_letter(row: usize) -> String {
+    match u8::try_from(row) {
+        Ok(row) if row < 26 => char::from(b'A' + row).to_string(),
+        _ => (row + 1).to_string(),
     }
 }
 
+fn panel_grid(panel: &PanelLayout, states: &[SlotState]) -> Vec<Option<usize>> {
+    println!(
+        "order {} total {} width {} height {}",
+        panel.order.name(),
+        states.len(),
+        panel.columns,
+        panel.rows
+    );
+    let grid = panel.grid(states.len());
+    let placed = grid.iter().flatten().count();
+    if placed < states.len() {
+        println!(
+            "[E] layout has room for {} of {} slots",
+            placed,
+            states.len()
+        );
+    }
+    grid
+}
+
 fn append_lines(slot_line_buffer: Vec<String>, output: &mut String, column_sep: &str) {
     for line in slot_line_buffer {
         output.push_str(&line);
@@ -162,7 +618,7 @@ fn huge_flag(flag: impl Borrow<Option<String>>, padding: usize) -> String {
 }
 
 fn huge_flag_str(flag: &Option<&str>, padding: usize) -> String {
-    huge_flag(flag.clone().map(|v| v.to_string()), padding)
+    huge_flag(flag.map(|v| v.to_string()), padding)
 }
 
 #[derive(PartialEq)]
@@ -215,60 +671,309 @@ pub struct SlotLine {
     pub line: String,
 }
 
-pub enum SlotPrintOrder {
-    TopLeftGoingDown,
-    BottomLeftGoingUp,
+/// Where slot 0 is, which way numbering moves first, and if every other line runs backwards
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SlotPrintOrder {
+    pub origin: Corner,
+    pub axis: Axis,
+    pub serpentine: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+/// Direction numbering moves first
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Axis {
+    /// Up or down a column, then the next column
+    Vertical,
+    /// Along a row, then the next row
+    Horizontal,
+}
+
+impl Corner {
+    fn is_top(&self) -> bool {
+        matches!(self, Corner::TopLeft | Corner::TopRight)
+    }
+
+    fn is_left(&self) -> bool {
+        matches!(self, Corner::TopLeft | Corner::BottomLeft)
+    }
+}
+
+impl FromStr for SlotPrintOrder {
+    type Err = String;
+
+    /// `bottom-left-going-up`, `top-right-going-left-serpentine` or `BottomLeftGoingUp`
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let unknown = || format!("unknown slot order {}", value);
+        let normalized = value.to_ascii_lowercase().replace(['-', '_'], "");
+
+        let (is_top, rest) = if let Some(rest) = normalized.strip_prefix("top") {
+            (true, rest)
+        } else if let Some(rest) = normalized.strip_prefix("bottom") {
+            (false, rest)
+        } else {
+            return Err(unknown());
+        };
+        let (is_left, rest) = if let Some(rest) = rest.strip_prefix("left") {
+            (true, rest)
+        } else if let Some(rest) = rest.strip_prefix("right") {
+            (false, rest)
+        } else {
+            return Err(unknown());
+        };
+        let rest = rest.strip_prefix("going").ok_or_else(unknown)?;
+        let (serpentine, direction) = match rest.strip_suffix("serpentine") {
+            Some(direction) => (true, direction),
+            None => (false, rest),
+        };
+        let axis = match (direction, is_top, is_left) {
+            ("down", true, _) | ("up", false, _) => Axis::Vertical,
+            ("right", _, true) | ("left", _, false) => Axis::Horizontal,
+            _ => return Err(unknown()),
+        };
+        let origin = match (is_top, is_left) {
+            (true, true) => Corner::TopLeft,
+            (true, false) => Corner::TopRight,
+            (false, true) => Corner::BottomLeft,
+            (false, false) => Corner::BottomRight,
+        };
+        Ok(Self::new(origin, axis, serpentine))
+    }
 }
 
 impl SlotPrintOrder {
-    fn order(&self, total_slots: usize, width: usize) -> Vec<usize> {
-        assert_eq!(total_slots % width, 0, "invalid width and slot count");
-        let height = total_slots / width;
-        assert_eq!(height * width, total_slots, "???");
-        println!(
-            "order {} total {} width {} height {}",
-            self.name(),
-            total_slots,
-            width,
-            height
-        );
+    pub const TOP_LEFT_GOING_DOWN: Self = Self::new(Corner::TopLeft, Axis::Vertical, false);
+    pub const BOTTOM_LEFT_GOING_UP: Self = Self::new(Corner::BottomLeft, Axis::Vertical, false);
+    pub const TOP_LEFT_GOING_RIGHT: Self = Self::new(Corner::TopLeft, Axis::Horizontal, false);
 
-        let mut res = Vec::new();
-        match *self {
-            SlotPrintOrder::TopLeftGoingDown => {
-                for h in 0..height {
-                    for w in 0..width {
-                        res.push((h) + (w * height));
-                    }
-                }
+    pub const fn new(origin: Corner, axis: Axis, serpentine: bool) -> Self {
+        Self {
+            origin,
+            axis,
+            serpentine,
+        }
+    }
+
+    #[cfg(test)]
+    fn order(&self, total_slots: usize, width: usize) -> Vec<Option<usize>> {
+        let height = total_slots.div_ceil(width);
+        self.grid(&vec![width; height], &[], total_slots)
+    }
+
+    /// Slot index for each cell of the grid, row by row from the top left.
+    /// Rows can be shorter than the widest row, the rest of the row has no slots.
+    /// `gaps` are positions in numbering order that have no slot, eg a PSU or fan module
+    pub fn grid(
+        &self,
+        row_lengths: &[usize],
+        gaps: &[usize],
+        total_slots: usize,
+    ) -> Vec<Option<usize>> {
+        let columns = row_lengths.iter().copied().max().unwrap_or(0);
+        let mut grid = vec![None; row_lengths.len() * columns];
+        let mut slot = 0;
+        for (position, cell) in self.numbering(row_lengths).into_iter().enumerate() {
+            if slot == total_slots {
+                break;
             }
-            SlotPrintOrder::BottomLeftGoingUp => {
-                for h in 0..height {
-                    for w in 0..width {
-                        res.push((height - h - 1) + (w * height));
-                    }
-                }
+            if gaps.contains(&position) {
+                continue;
             }
+            grid[cell] = Some(slot);
+            slot += 1;
         }
-        res
+        grid
     }
 
-    fn name(&self) -> &str {
-        match *self {
-            SlotPrintOrder::TopLeftGoingDown => "TopLeftGoingDown",
-            SlotPrintOrder::BottomLeftGoingUp => "BottomLeftGoingUp",
+    /// Cells in the order slots are numbered
+    fn numbering(&self, row_lengths: &[usize]) -> Vec<usize> {
+        let rows = row_lengths.len();
+        let columns = row_lengths.iter().copied().max().unwrap_or(0);
+        let row_indexes: Vec<usize> = if self.origin.is_top() {
+            (0..rows).collect()
+        } else {
+            (0..rows).rev().collect()
+        };
+        let column_indexes: Vec<usize> = if self.origin.is_left() {
+            (0..columns).collect()
+        } else {
+            (0..columns).rev().collect()
+        };
+        let (outer, inner) = match self.axis {
+            Axis::Vertical => (&column_indexes, &row_indexes),
+            Axis::Horizontal => (&row_indexes, &column_indexes),
+        };
+
+        let mut res = Vec::with_capacity(rows * columns);
+        for (line, outer_index) in outer.iter().enumerate() {
+            let reverse = self.serpentine && line % 2 == 1;
+            let mut cells: Vec<(usize, usize)> = inner
+                .iter()
+                .map(|inner_index| match self.axis {
+                    Axis::Vertical => (*inner_index, *outer_index),
+                    Axis::Horizontal => (*outer_index, *inner_index),
+                })
+                .filter(|(row, column)| *column < row_lengths[*row])
+                .collect();
+            if reverse {
+                cells.reverse();
+            }
+            res.extend(
+                cells
+                    .into_iter()
+                    .map(|(row, column)| row * columns + column),
+            );
         }
+        res
+    }
+
+    /// `top-left-going-down` style name, also accepted by [SlotPrintOrder::from_str]
+    pub fn name(&self) -> String {
+        let vertical = if self.origin.is_top() {
+            "top"
+        } else {
+            "bottom"
+        };
+        let horizontal = if self.origin.is_left() {
+            "left"
+        } else {
+            "right"
+        };
+        let direction = match (self.axis, self.origin.is_top(), self.origin.is_left()) {
+            (Axis::Vertical, true, _) => "down",
+            (Axis::Vertical, false, _) => "up",
+            (Axis::Horizontal, _, true) => "right",
+            (Axis::Horizontal, _, false) => "left",
+        };
+        let serpentine = if self.serpentine { "-serpentine" } else { "" };
+        format!(
+            "{}-{}-going-{}{}",
+            vertical, horizontal, direction, serpentine
+        )
     }
 }
 
 #[cfg(test)]
 mod test {
-    use super::SlotPrintOrder;
+    use super::{
+        split_panels, wrap, Axis, ConsoleViewer, Corner, SlotLabel, SlotPrintOrder, SlotState,
+        U_LEFT_ONE_EIGHTH_BLOCK, U_LOWER_ONE_EIGHTH_BLOCK,
+    };
+    use crate::layout::{PanelLayout, PanelView};
+
+    fn device(name: &str) -> SlotState {
+        SlotState::Device(
+            name.to_string(),
+            SlotLabel {
+                content_start: String::new(),
+                content_end: name.to_string(),
+                prefix: None,
+                suffix: None,
+            },
+            Vec::new(),
+        )
+    }
+
+    fn panel(name: &str, columns: usize, view: PanelView) -> PanelLayout {
+        PanelLayout {
+            name: name.into(),
+            view,
+            ..PanelLayout::with_width(columns, columns, SlotPrintOrder::TOP_LEFT_GOING_RIGHT)
+        }
+    }
+
+    #[test]
+    fn test_split_panels() {
+        let rear = PanelLayout {
+            enclosure: Some("0:0:2:0".into()),
+            ..panel("rear", 2, PanelView::Bays)
+        };
+        let panels = [panel("front", 3, PanelView::Bays), rear];
+        let mut asked = Vec::new();
+        let split = split_panels(&panels, vec![0, 1, 2, 3], |panel, id| {
+            asked.push((panel.name.clone(), id.to_string()));
+            Ok(Some(vec![10, 11]))
+        })
+        .unwrap();
+        // the front is the only panel of its own, overflow stays on it
+        assert_eq!(split, [vec![0, 1, 2, 3], vec![10, 11]]);
+        assert_eq!(asked, [("rear".to_string(), "0:0:2:0".to_string())]);
+
+        let split = split_panels(&panels, vec![0, 1], |_, _| Ok(None)).unwrap();
+        assert_eq!(split, [vec![0, 1], vec![]]);
+    }
+
+    #[test]
+    fn test_split_panels_overflow() {
+        let panels = [
+            panel("top", 2, PanelView::Drawer),
+            panel("middle", 2, PanelView::Drawer),
+            panel("bottom", 2, PanelView::Drawer),
+        ];
+        let split = split_panels(&panels, (0..7).collect(), |_, _| Ok(None)).unwrap();
+        assert_eq!(split, [vec![0, 1], vec![2, 3], vec![4, 5, 6]]);
+        let split = split_panels(&panels, (0..3).collect(), |_, _| Ok(None)).unwrap();
+        assert_eq!(split, [vec![0, 1], vec![2], vec![]]);
+    }
+
+    #[test]
+    fn test_title_wraps_to_grid() {
+        let viewer = ConsoleViewer {
+            title: Some("LSI SAS2X28 (2U 24x 2.5) - 0:0:15:0 [running*] 1:0:15:0".into()),
+            panels: vec![panel("front", 4, PanelView::Vertical)],
+            legend: None,
+        };
+        let states = [device("sda"), device("sdb"), device("sdc"), device("sdd")];
+        let output = viewer.render_panels(&[&states]);
+        let mut lines = output.lines();
+        assert_eq!(lines.next().unwrap().chars().count(), 16);
+        // title lines up to the first row of slots
+        let title: Vec<&str> = lines
+            .take_while(|line| !line.starts_with(U_LOWER_ONE_EIGHTH_BLOCK))
+            .collect();
+        assert!(title.len() > 1, "{output}");
+        assert!(
+            title.iter().all(|line| line.chars().count() == 16),
+            "{output}"
+        );
+        assert!(title.contains(&"----0:0:15:0----"), "{output}");
+        assert_eq!(wrap("tank", 2), ["ta", "nk"]);
+        assert_eq!(wrap("", 16), [""]);
+    }
+
+    #[test]
+    fn test_drawers_share_width() {
+        let viewer = ConsoleViewer {
+            title: None,
+            panels: vec![
+                panel("top", 2, PanelView::Drawer),
+                panel("bottom", 2, PanelView::Drawer),
+            ],
+            legend: None,
+        };
+        let top = [device("sda"), device("sdb")];
+        let bottom = [device("nvme10n1"), device("sdd")];
+        let output = viewer.render_panels(&[&top, &bottom]);
+        let widths: Vec<usize> = output
+            .lines()
+            .filter(|line| line.contains(U_LEFT_ONE_EIGHTH_BLOCK))
+            .map(|line| line.chars().count())
+            .collect();
+        assert_eq!(widths.len(), 2);
+        assert_eq!(widths[0], widths[1], "{output}");
+    }
 
     #[test]
     fn test_going_down() {
         assert_eq!(
-            SlotPrintOrder::TopLeftGoingDown.order(4, 2),
+            SlotPrintOrder::TOP_LEFT_GOING_DOWN.order(4, 2),
             shelf([
                 [0, 2], //
                 [1, 3]  //
@@ -276,7 +981,7 @@ mod test {
             "4,2"
         );
         assert_eq!(
-            SlotPrintOrder::TopLeftGoingDown.order(9, 3),
+            SlotPrintOrder::TOP_LEFT_GOING_DOWN.order(9, 3),
             shelf([
                 [0, 3, 6], //
                 [1, 4, 7], //
@@ -289,7 +994,7 @@ mod test {
     #[test]
     fn test_going_up() {
         assert_eq!(
-            SlotPrintOrder::BottomLeftGoingUp.order(4, 2),
+            SlotPrintOrder::BOTTOM_LEFT_GOING_UP.order(4, 2),
             shelf([
                 [1, 3], //
                 [0, 2]  //
@@ -297,7 +1002,7 @@ mod test {
             "4,2"
         );
         assert_eq!(
-            SlotPrintOrder::BottomLeftGoingUp.order(9, 3),
+            SlotPrintOrder::BOTTOM_LEFT_GOING_UP.order(9, 3),
             shelf([
                 [2, 5, 8], //
                 [1, 4, 7], //
@@ -307,7 +1012,204 @@ mod test {
         );
     }
 
-    fn shelf<const X: usize, const Y: usize>(order: [[usize; X]; Y]) -> Vec<usize> {
-        order.into_iter().flatten().collect()
+    #[test]
+    fn test_columns() {
+        let order = |spec: &str| spec.parse::<SlotPrintOrder>().unwrap().order(6, 3);
+        assert_eq!(
+            order("top-left-going-down"),
+            shelf([
+                [0, 2, 4], //
+                [1, 3, 5]  //
+            ])
+        );
+        assert_eq!(
+            order("top-left-going-down-serpentine"),
+            shelf([
+                [0, 3, 4], //
+                [1, 2, 5]  //
+            ])
+        );
+        assert_eq!(
+            order("top-right-going-down"),
+            shelf([
+                [4, 2, 0], //
+                [5, 3, 1]  //
+            ])
+        );
+        assert_eq!(
+            order("top-right-going-down-serpentine"),
+            shelf([
+                [4, 3, 0], //
+                [5, 2, 1]  //
+            ])
+        );
+        assert_eq!(
+            order("bottom-left-going-up"),
+            shelf([
+                [1, 3, 5], //
+                [0, 2, 4]  //
+            ])
+        );
+        assert_eq!(
+            order("bottom-left-going-up-serpentine"),
+            shelf([
+                [1, 2, 5], //
+                [0, 3, 4]  //
+            ])
+        );
+        assert_eq!(
+            order("bottom-right-going-up"),
+            shelf([
+                [5, 3, 1], //
+                [4, 2, 0]  //
+            ])
+        );
+        assert_eq!(
+            order("bottom-right-going-up-serpentine"),
+            shelf([
+                [5, 2, 1], //
+                [4, 3, 0]  //
+            ])
+        );
+    }
+
+    #[test]
+    fn test_rows() {
+        let order = |spec: &str| spec.parse::<SlotPrintOrder>().unwrap().order(6, 3);
+        assert_eq!(
+            order("top-left-going-right"),
+            shelf([
+                [0, 1, 2], //
+                [3, 4, 5]  //
+            ])
+        );
+        assert_eq!(
+            order("top-left-going-right-serpentine"),
+            shelf([
+                [0, 1, 2], //
+                [5, 4, 3]  //
+            ])
+        );
+        assert_eq!(
+            order("top-right-going-left"),
+            shelf([
+                [2, 1, 0], //
+                [5, 4, 3]  //
+            ])
+        );
+        assert_eq!(
+            order("top-right-going-left-serpentine"),
+            shelf([
+                [2, 1, 0], //
+                [3, 4, 5]  //
+            ])
+        );
+        assert_eq!(
+            order("bottom-left-going-right"),
+            shelf([
+                [3, 4, 5], //
+                [0, 1, 2]  //
+            ])
+        );
+        assert_eq!(
+            order("bottom-left-going-right-serpentine"),
+            shelf([
+                [5, 4, 3], //
+                [0, 1, 2]  //
+            ])
+        );
+        assert_eq!(
+            order("bottom-right-going-left"),
+            shelf([
+                [5, 4, 3], //
+                [2, 1, 0]  //
+            ])
+        );
+        assert_eq!(
+            order("bottom-right-going-left-serpentine"),
+            shelf([
+                [3, 4, 5], //
+                [2, 1, 0]  //
+            ])
+        );
+    }
+
+    #[test]
+    fn test_order_names() {
+        assert_eq!(
+            "BottomLeftGoingUp".parse(),
+            Ok(SlotPrintOrder::BOTTOM_LEFT_GOING_UP)
+        );
+        assert!("top-left-going-up".parse::<SlotPrintOrder>().is_err());
+        for origin in [
+            Corner::TopLeft,
+            Corner::TopRight,
+            Corner::BottomLeft,
+            Corner::BottomRight,
+        ] {
+            for axis in [Axis::Vertical, Axis::Horizontal] {
+                for serpentine in [false, true] {
+                    let order = SlotPrintOrder::new(origin, axis, serpentine);
+                    assert_eq!(order.name().parse(), Ok(order));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_ragged() {
+        assert_eq!(
+            SlotPrintOrder::TOP_LEFT_GOING_DOWN.order(5, 2),
+            [
+                [Some(0), Some(3)], //
+                [Some(1), Some(4)], //
+                [Some(2), None]
+            ]
+            .concat(),
+            "5,2"
+        );
+        assert_eq!(
+            SlotPrintOrder::BOTTOM_LEFT_GOING_UP.order(45, 4).len(),
+            48,
+            "45,4"
+        );
+    }
+
+    #[test]
+    fn test_row_lengths() {
+        // 4U60+2 style, short row at the bottom
+        assert_eq!(
+            SlotPrintOrder::TOP_LEFT_GOING_DOWN.grid(&[3, 3, 1], &[], 7),
+            [
+                [Some(0), Some(3), Some(5)], //
+                [Some(1), Some(4), Some(6)], //
+                [Some(2), None, None]
+            ]
+            .concat()
+        );
+        assert_eq!(
+            SlotPrintOrder::BOTTOM_LEFT_GOING_UP.grid(&[1, 3], &[], 4),
+            [
+                [Some(1), None, None], //
+                [Some(0), Some(2), Some(3)]
+            ]
+            .concat()
+        );
+    }
+
+    #[test]
+    fn test_gaps() {
+        assert_eq!(
+            SlotPrintOrder::BOTTOM_LEFT_GOING_UP.grid(&[3, 3], &[3], 5),
+            [
+                [Some(1), None, Some(4)], //
+                [Some(0), Some(2), Some(3)]
+            ]
+            .concat()
+        );
+    }
+
+    fn shelf<const X: usize, const Y: usize>(order: [[usize; X]; Y]) -> Vec<Option<usize>> {
+        order.into_iter().flatten().map(Some).collect()
     }
 }
@@ -1,40 +1,76 @@
 use std::{
-    fs::{read_dir, read_to_string},
+    collections::BTreeMap,
+    fmt::Display,
+    fs::{read_dir, OpenOptions},
+    io::Write,
     path::{Path, PathBuf},
 };
 
 use crate::{
+    blockdev::BlockResolver,
     err::{io_op, io_op_call, SError, SResult},
+    sysfs::SysfsRoot,
     utils::{
         into_not_found_option_or_panic_io, into_not_found_option_or_panic_s,
         read_dir_with_single_file, read_to_string_trim,
     },
 };
 
-const ENCLOSURE_DIR: &str = "/sys/class/enclosure";
-
 #[derive(Debug)]
 pub struct Enclosure {
+    sysfs: SysfsRoot,
+    /// Path used to read slots, the first active one
     enc_id: String,
+    /// Every enc_id this shelf is seen through, with dual HBAs or expanders
+    paths: Vec<String>,
+    logical_id: Option<String>,
 }
 
 impl Enclosure {
-    pub fn load_all() -> SResult<Vec<Self>> {
-        Ok(Self::find_enclosure_ids()?
-            .into_iter()
-            .map(|enc_id| Self { enc_id })
-            .collect())
+    /// One Enclosure per physical shelf, grouping paths with the same logical id (SAS address)
+    pub fn load_all(sysfs: &SysfsRoot) -> SResult<Vec<Self>> {
+        let mut enclosures: Vec<Self> = Vec::new();
+        for enc_id in Self::find_enclosure_ids(sysfs)? {
+            let id_path = sysfs.enclosure_dir().join(&enc_id).join("id");
+            let logical_id = read_to_string_trim(&id_path).ok();
+
+            match enclosures
+                .iter_mut()
+                .find(|e| logical_id.is_some() && e.logical_id == logical_id)
+            {
+                Some(enclosure) => enclosure.paths.push(enc_id),
+                None => enclosures.push(Self {
+                    sysfs: sysfs.clone(),
+                    enc_id: enc_id.clone(),
+                    paths: vec![enc_id],
+                    logical_id,
+                }),
+            }
+        }
+
+        for enclosure in &mut enclosures {
+            if let Some(active) = enclosure
+                .paths
+                .iter()
+                .find(|path| enclosure.is_path_active(path))
+            {
+                enclosure.enc_id = active.clone();
+            }
+        }
+        Ok(enclosures)
     }
 
-    fn find_enclosure_ids() -> SResult<Vec<String>> {
-        let read = io_op_call(read_dir, ENCLOSURE_DIR)?;
+    fn find_enclosure_ids(sysfs: &SysfsRoot) -> SResult<Vec<String>> {
+        let enclosure_dir = sysfs.enclosure_dir();
+        let read = io_op_call(read_dir, &enclosure_dir)?;
         let enclosures: SResult<Vec<String>> = read
             .map(|file| {
-                let file = io_op(file, ENCLOSURE_DIR)?;
+                let file = io_op(file, &enclosure_dir)?;
                 SResult::Ok(file.file_name().to_string_lossy().to_string())
             })
             .collect();
-        let enclosures = enclosures?;
+        let mut enclosures = enclosures?;
+        enclosures.sort();
         if enclosures.is_empty() {
             Err(SError::NoEnclosuresFound)
         } else {
@@ -46,48 +82,140 @@ impl Enclosure {
         &self.enc_id
     }
 
-    pub fn slot_len(&self) -> SResult<usize> {
-        let components_path = self.files(&["components"]);
-        let slots = io_op_call(read_to_string, &components_path)?;
-        Ok(slots.trim().parse().map_err(|_| SError::ComponentsNaN {
-            path: components_path,
-        })?)
+    pub fn paths(&self) -> &[String] {
+        &self.paths
+    }
+
+    pub fn logical_id(&self) -> Option<&str> {
+        self.logical_id.as_deref()
+    }
+
+    /// Any of the enc_id paths or the logical id
+    pub fn has_id(&self, id: &str) -> bool {
+        self.paths.iter().any(|path| path == id) || self.logical_id() == Some(id)
+    }
+
+    /// SCSI device state of the path, `running` when usable
+    pub fn path_state(&self, enc_id: &str) -> Option<String> {
+        let path = self.sysfs.enclosure_dir().join(enc_id).join("device/state");
+        read_to_string_trim(path).ok()
+    }
+
+    /// Paths without a state file are assumed to work
+    pub fn is_path_active(&self, enc_id: &str) -> bool {
+        self.path_state(enc_id)
+            .is_none_or(|state| state == "running")
+    }
+
+    /// Every component directory keyed by its slot number.
+    /// Firmwares name them `Slot00`, `Slot 1`, `Disk001`, `ArrayDevice00`, `0`, or descriptor text
+    pub fn slots(&self) -> SResult<BTreeMap<usize, Slot>> {
+        let mut slots = BTreeMap::new();
+        let mut unnumbered = Vec::new();
+        for component in self.find_component_names()? {
+            let slot_path = self.files([&component, "slot"]);
+            let slot_id = match into_not_found_option_or_panic_s(
+                &slot_path,
+                io_op_call(read_to_string_trim, &slot_path),
+            ) {
+                Some(slot) => slot
+                    .parse()
+                    .map_err(|_| SError::SlotNaN { path: slot_path })?,
+                // older kernels don't have the attribute
+                None => match trailing_number(&component) {
+                    Some(slot_id) => slot_id,
+                    None => {
+                        unnumbered.push(component);
+                        continue;
+                    }
+                },
+            };
+            self.insert_slot(&mut slots, slot_id, component);
+        }
+
+        // after the real slots so they can't take one of their numbers
+        let next_id = slots.keys().next_back().map_or(0, |last| last + 1);
+        for (slot_id, component) in (next_id..).zip(unnumbered) {
+            self.insert_slot(&mut slots, slot_id, component);
+        }
+        Ok(slots)
     }
 
-    pub fn slot(&self, slot_id: usize) -> Slot {
-        Slot {
-            enc_id: self.enc_id.clone(),
+    fn insert_slot(&self, slots: &mut BTreeMap<usize, Slot>, slot_id: usize, component: String) {
+        if slots.contains_key(&slot_id) {
+            println!(
+                "[E] duplicate slot {} in {}, ignoring {}",
+                slot_id, self.enc_id, component
+            );
+            return;
+        }
+        slots.insert(
             slot_id,
+            Slot {
+                sysfs: self.sysfs.clone(),
+                enc_id: self.enc_id.clone(),
+                component,
+                slot_id,
+            },
+        );
+    }
+
+    fn find_component_names(&self) -> SResult<Vec<String>> {
+        let root = self.root();
+        let mut components = Vec::new();
+        for file in io_op_call(read_dir, &root)? {
+            let file = io_op(file, &root)?;
+            let path = file.path();
+            // components have these attributes, device/ power/ subsystem/ don't
+            if path.join("type").exists() || path.join("slot").exists() {
+                components.push(file.file_name().to_string_lossy().to_string());
+            }
         }
+        components.sort();
+        Ok(components)
     }
 
     pub fn device_vendor(&self) -> SResult<String> {
-        let path = self.files(&["device", "vendor"]);
+        let path = self.files(["device", "vendor"]);
         io_op_call(read_to_string_trim, &path)
     }
 
     pub fn device_model(&self) -> SResult<String> {
-        let path = self.files(&["device", "model"]);
+        let path = self.files(["device", "model"]);
         io_op_call(read_to_string_trim, &path)
     }
 }
 
 impl HasFiles for Enclosure {
     fn root(&self) -> PathBuf {
-        let mut path = PathBuf::from(ENCLOSURE_DIR);
+        let mut path = self.sysfs.enclosure_dir();
         path.push(&self.enc_id);
         path
     }
 }
 
 pub struct Slot {
+    sysfs: SysfsRoot,
     enc_id: String,
+    component: String,
     slot_id: usize,
 }
 
 impl Slot {
+    pub fn enc_id(&self) -> &str {
+        &self.enc_id
+    }
+
+    pub fn slot_id(&self) -> usize {
+        self.slot_id
+    }
+
+    pub fn component(&self) -> &str {
+        &self.component
+    }
+
     pub fn block_path(&self) -> Option<PathBuf> {
-        let block_root_dir = self.files(&["device", "block"]);
+        let block_root_dir = self.files(["device", "block"]);
         into_not_found_option_or_panic_s(
             &block_root_dir,
             read_dir_with_single_file(&block_root_dir),
@@ -106,39 +234,294 @@ impl Slot {
     }
 
     pub fn device_wwid(&self) -> Option<String> {
-        let path = self.files(&["device", "wwid"]);
+        let path = self.files(["device", "wwid"]);
         into_not_found_option_or_panic_io(&path, read_to_string_trim(&path))
     }
 
     pub fn device_vendor(&self) -> SResult<String> {
-        let path = self.files(&["device", "vendor"]);
+        let path = self.files(["device", "vendor"]);
         io_op_call(read_to_string_trim, &path)
     }
 
     pub fn device_model(&self) -> SResult<String> {
-        let path = self.files(&["device", "model"]);
+        let path = self.files(["device", "model"]);
         io_op_call(read_to_string_trim, &path)
     }
 
     pub fn is_locating(&self) -> bool {
-        let path: PathBuf = self.file("locate");
-        let read = into_not_found_option_or_panic_io(&path, read_to_string_trim(&path));
-        match read {
-            Some(content) if content == "1" => true,
-            _ => false,
+        self.read_flag("locate").unwrap_or(false)
+    }
+
+    pub fn status(&self) -> Option<SlotStatus> {
+        SlotStatus::from_sysfs(&self.read_attribute("status")?)
+    }
+
+    pub fn slot_type(&self) -> Option<SlotType> {
+        SlotType::from_sysfs(&self.read_attribute("type")?)
+    }
+
+    pub fn indicators(&self) -> Indicators {
+        Indicators {
+            locate: self.is_locating(),
+            fault: self.read_flag("fault").unwrap_or(false),
+            active: self.read_flag("active").unwrap_or(false),
+            power_on: self
+                .read_attribute("power_status")
+                .map(|power| power == "on"),
+        }
+    }
+
+    pub fn set_locate(&self, locate: bool) -> SResult<()> {
+        self.write_flag("locate", locate)
+    }
+
+    pub fn set_fault(&self, fault: bool) -> SResult<()> {
+        self.write_flag("fault", fault)
+    }
+
+    fn write_flag(&self, file: &str, value: bool) -> SResult<()> {
+        let path = self.file(file);
+        let content = if value { "1" } else { "0" };
+        // without create, a missing attribute is NotFound instead of a new file
+        OpenOptions::new()
+            .write(true)
+            .open(&path)
+            .and_then(|mut file| file.write_all(content.as_bytes()))
+            .map_err(SError::attribute_write(path))
+    }
+
+    fn read_flag(&self, file: &str) -> Option<bool> {
+        self.read_attribute(file).map(|content| content == "1")
+    }
+
+    /// The ses driver returns EIO/EINVAL for attributes the firmware doesn't report
+    fn read_attribute(&self, file: &str) -> Option<String> {
+        read_to_string_trim(self.file(file)).ok()
+    }
+}
+
+/// Turns locate on and puts it back to how it was before, on restore or drop.
+/// A light that was already on before stays on
+pub struct LocateGuard<'s> {
+    slot: &'s Slot,
+    previous: Option<bool>,
+}
+
+impl<'s> LocateGuard<'s> {
+    pub fn new(slot: &'s Slot) -> SResult<Self> {
+        let previous = slot.is_locating();
+        slot.set_locate(true)?;
+        Ok(Self {
+            slot,
+            previous: Some(previous),
+        })
+    }
+
+    pub fn previous(&self) -> bool {
+        self.previous.unwrap_or_default()
+    }
+
+    pub fn restore(mut self) -> SResult<()> {
+        self.restore_inner()
+    }
+
+    fn restore_inner(&mut self) -> SResult<()> {
+        match self.previous.take() {
+            Some(previous) => self.slot.set_locate(previous),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for LocateGuard<'_> {
+    fn drop(&mut self) {
+        if let Err(err) = self.restore_inner() {
+            println!("[E] failed to restore locate {}", err);
+        }
+    }
+}
+
+/// How the user addresses a slot on the command line
+#[derive(Debug, Clone, PartialEq)]
+pub enum SlotTarget {
+    /// `0:0:15:0/5`
+    Slot { enc_id: String, slot_id: usize },
+    /// `sdb`, `/dev/sdb1` or a `/dev/disk/by-id/...` link, with the whole disks under it
+    BlockName { name: String, disks: Vec<String> },
+    /// `naa.5000c500...`, `wwn-0x5000c500...`, or `0x5000c500...`
+    Wwid(String),
+}
+
+impl SlotTarget {
+    pub fn parse(value: &str, resolver: &BlockResolver) -> Self {
+        if value.starts_with("/dev/") {
+            let block_name = resolver.kernel_name(value);
+            // a by-id link udev hasn't created, the wwn is still in its name
+            if normalize_wwid(&block_name).is_some() {
+                return Self::Wwid(block_name);
+            }
+            return Self::block_name(value, resolver);
+        }
+        if let Some((enc_id, slot_id)) = value.rsplit_once('/') {
+            if let Ok(slot_id) = slot_id.parse() {
+                return Self::Slot {
+                    enc_id: enc_id.to_string(),
+                    slot_id,
+                };
+            }
+        }
+        if normalize_wwid(value).is_some() {
+            return Self::Wwid(value.to_string());
         }
+        Self::block_name(value, resolver)
     }
+
+    /// Slots only know the whole disk, not a partition or dm device on it
+    fn block_name(value: &str, resolver: &BlockResolver) -> Self {
+        Self::BlockName {
+            name: value.to_string(),
+            disks: resolver.whole_disks(value),
+        }
+    }
+
+    pub fn find(&self, enclosures: &[Enclosure]) -> SResult<Slot> {
+        for enclosure in enclosures {
+            for (slot_id, slot) in enclosure.slots()? {
+                let found = match self {
+                    Self::Slot {
+                        enc_id,
+                        slot_id: target_id,
+                    } => enclosure.paths().contains(enc_id) && *target_id == slot_id,
+                    Self::BlockName { disks, .. } => slot
+                        .block_name()
+                        .is_some_and(|block_name| disks.contains(&block_name)),
+                    Self::Wwid(wwid) => normalize_wwid(wwid).is_some_and(|wwid| {
+                        slot.device_wwid().as_deref().and_then(normalize_wwid) == Some(wwid)
+                    }),
+                };
+                if found {
+                    return Ok(slot);
+                }
+            }
+        }
+        Err(SError::SlotNotFound {
+            target: self.to_string(),
+        })
+    }
+}
+
+impl Display for SlotTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Slot { enc_id, slot_id } => write!(f, "{}/{}", enc_id, slot_id),
+            Self::BlockName { name, .. } => write!(f, "{}", name),
+            Self::Wwid(wwid) => write!(f, "{}", wwid),
+        }
+    }
+}
+
+/// sysfs says `naa.5000c500...` while /dev/disk/by-id says `wwn-0x5000c500...`
+fn normalize_wwid(wwid: &str) -> Option<String> {
+    let wwid = wwid.to_ascii_lowercase();
+    let hex = ["naa.", "wwn-0x", "0x", "eui.", "t10."]
+        .iter()
+        .find_map(|prefix| wwid.strip_prefix(prefix))?;
+    Some(hex.to_string())
+}
+
+/// `status` of an enclosure component, see `enclosure_status` in drivers/misc/enclosure.c
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SlotStatus {
+    Unsupported,
+    Ok,
+    Critical,
+    NonCritical,
+    Unrecoverable,
+    NotInstalled,
+    Unknown,
+    Unavailable,
+    NoAccess,
+}
+
+impl SlotStatus {
+    fn from_sysfs(value: &str) -> Option<Self> {
+        Some(match value {
+            "unsupported" => Self::Unsupported,
+            "OK" => Self::Ok,
+            "critical" => Self::Critical,
+            "non-critical" => Self::NonCritical,
+            "unrecoverable" => Self::Unrecoverable,
+            "not installed" => Self::NotInstalled,
+            "unknown" => Self::Unknown,
+            "unavailable" => Self::Unavailable,
+            "no access" => Self::NoAccess,
+            _ => return None,
+        })
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Unsupported => "unsupported",
+            Self::Ok => "OK",
+            Self::Critical => "critical",
+            Self::NonCritical => "non-critical",
+            Self::Unrecoverable => "unrecoverable",
+            Self::NotInstalled => "not installed",
+            Self::Unknown => "unknown",
+            Self::Unavailable => "unavailable",
+            Self::NoAccess => "no access",
+        }
+    }
+
+    pub fn is_failed(&self) -> bool {
+        matches!(self, Self::Critical | Self::Unrecoverable)
+    }
+
+    /// SES reports a predicted failure as non-critical
+    pub fn is_predicted_failure(&self) -> bool {
+        matches!(self, Self::NonCritical)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SlotType {
+    Device,
+    ArrayDevice,
+}
+
+impl SlotType {
+    fn from_sysfs(value: &str) -> Option<Self> {
+        match value {
+            "device" => Some(Self::Device),
+            "array device" => Some(Self::ArrayDevice),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Indicators {
+    pub locate: bool,
+    pub fault: bool,
+    pub active: bool,
+    /// None when the firmware doesn't report power
+    pub power_on: Option<bool>,
 }
 
 impl HasFiles for Slot {
     fn root(&self) -> PathBuf {
-        let mut path = PathBuf::from(ENCLOSURE_DIR);
+        let mut path = self.sysfs.enclosure_dir();
         path.push(&self.enc_id);
-        path.push(&format!("Slot{:02}", self.slot_id));
+        path.push(&self.component);
         path
     }
 }
 
+fn trailing_number(name: &str) -> Option<usize> {
+    let digits = name.len() - name.trim_end_matches(|c: char| c.is_ascii_digit()).len();
+    name[(name.len() - digits)..].parse().ok()
+}
+
 trait HasFiles {
     fn root(&self) -> PathBuf;
 
@@ -151,6 +534,237 @@ trait HasFiles {
     }
 
     fn file(&self, file: impl AsRef<Path>) -> PathBuf {
-        self.files(&[file])
+        self.files([file])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        trailing_number, Enclosure, Indicators, LocateGuard, SlotStatus, SlotTarget, SlotType,
+    };
+    use crate::{blockdev::BlockResolver, err::SError, sysfs::fixture::SysfsFixture};
+
+    #[test]
+    fn test_load_from_fixture_root() {
+        let fixture = SysfsFixture::new("enclosure-root");
+        fixture
+            .file("class/enclosure/0:0:15:0/components", "2")
+            .file("class/enclosure/0:0:15:0/device/vendor", "LSI     ")
+            .file("class/enclosure/0:0:15:0/device/model", "SAS2X36")
+            .file("class/enclosure/0:0:15:0/Slot00/type", "array device")
+            .file("class/enclosure/0:0:15:0/Slot00/locate", "1")
+            .dir("class/enclosure/0:0:15:0/Slot00/device/block/sdb")
+            .file("class/enclosure/0:0:15:0/Slot01/type", "array device")
+            .file("class/enclosure/0:0:15:0/Slot01/locate", "0");
+
+        let enclosures = Enclosure::load_all(&fixture.sysfs()).unwrap();
+        assert_eq!(enclosures.len(), 1);
+        let enclosure = &enclosures[0];
+        assert_eq!(enclosure.enc_id(), "0:0:15:0");
+        assert_eq!(enclosure.device_vendor().unwrap(), "LSI");
+
+        let slots = enclosure.slots().unwrap();
+        assert_eq!(slots.len(), 2);
+
+        let slot = &slots[&0];
+        assert!(slot.is_locating());
+        assert_eq!(slot.block_name().as_deref(), Some("sdb"));
+
+        let slot = &slots[&1];
+        assert!(!slot.is_locating());
+        assert_eq!(slot.block_name(), None);
+    }
+
+    #[test]
+    fn test_slot_status() {
+        let fixture = SysfsFixture::new("enclosure-status");
+        fixture
+            .file("class/enclosure/0:0:15:0/Slot00/type", "array device")
+            .file("class/enclosure/0:0:15:0/Slot00/status", "critical")
+            .file("class/enclosure/0:0:15:0/Slot00/fault", "1")
+            .file("class/enclosure/0:0:15:0/Slot00/active", "0")
+            .file("class/enclosure/0:0:15:0/Slot00/locate", "0")
+            .file("class/enclosure/0:0:15:0/Slot00/power_status", "on")
+            .file("class/enclosure/0:0:15:0/Slot01/type", "device")
+            .file("class/enclosure/0:0:15:0/Slot01/status", "OK");
+
+        let enclosures = Enclosure::load_all(&fixture.sysfs()).unwrap();
+        let slots = enclosures[0].slots().unwrap();
+
+        let slot = &slots[&0];
+        assert_eq!(slot.status(), Some(SlotStatus::Critical));
+        assert!(slot.status().unwrap().is_failed());
+        assert_eq!(slot.slot_type(), Some(SlotType::ArrayDevice));
+        assert_eq!(
+            slot.indicators(),
+            Indicators {
+                locate: false,
+                fault: true,
+                active: false,
+                power_on: Some(true),
+            }
+        );
+
+        let slot = &slots[&1];
+        assert_eq!(slot.status(), Some(SlotStatus::Ok));
+        assert_eq!(slot.slot_type(), Some(SlotType::Device));
+        assert_eq!(slot.indicators().power_on, None);
+    }
+
+    #[test]
+    fn test_multipath_enclosures() {
+        let fixture = SysfsFixture::new("enclosure-multipath");
+        fixture
+            .file("class/enclosure/0:0:15:0/id", "0x500605b000000001")
+            .file("class/enclosure/0:0:15:0/device/state", "offline")
+            .file("class/enclosure/1:0:15:0/id", "0x500605b000000001")
+            .file("class/enclosure/1:0:15:0/device/state", "running")
+            .file("class/enclosure/1:0:15:0/Slot00/type", "array device")
+            .file("class/enclosure/2:0:15:0/id", "0x500605b000000002");
+
+        let enclosures = Enclosure::load_all(&fixture.sysfs()).unwrap();
+        assert_eq!(enclosures.len(), 2);
+
+        let enclosure = &enclosures[0];
+        assert_eq!(enclosure.paths(), ["0:0:15:0", "1:0:15:0"]);
+        assert_eq!(enclosure.logical_id(), Some("0x500605b000000001"));
+        assert_eq!(enclosure.enc_id(), "1:0:15:0");
+        assert!(!enclosure.is_path_active("0:0:15:0"));
+        assert_eq!(enclosure.slots().unwrap().len(), 1);
+
+        let resolver = BlockResolver::from_sysfs(&fixture.sysfs());
+        let slot = SlotTarget::parse("0:0:15:0/0", &resolver)
+            .find(&enclosures)
+            .unwrap();
+        assert_eq!(slot.enc_id(), "1:0:15:0");
+
+        assert_eq!(enclosures[1].paths(), ["2:0:15:0"]);
+    }
+
+    #[test]
+    fn test_slots_from_other_vendors() {
+        let fixture = SysfsFixture::new("enclosure-vendors");
+        fixture
+            // numbered from 1 with the slot attribute
+            .file("class/enclosure/1:0:0:0/Slot 1/type", "array device")
+            .file("class/enclosure/1:0:0:0/Slot 1/slot", "1")
+            .file("class/enclosure/1:0:0:0/Slot 2/type", "array device")
+            .file("class/enclosure/1:0:0:0/Slot 2/slot", "2")
+            // descriptor text
+            .file("class/enclosure/1:0:0:0/Front Bay Left/type", "device")
+            .file("class/enclosure/1:0:0:0/Front Bay Left/slot", "3")
+            // no slot attribute
+            .file("class/enclosure/1:0:0:0/Disk004/type", "device")
+            // neither, numbered after the others
+            .file("class/enclosure/1:0:0:0/Rear Bay/type", "device")
+            .dir("class/enclosure/1:0:0:0/power")
+            .file("class/enclosure/1:0:0:0/components", "4");
+
+        let enclosures = Enclosure::load_all(&fixture.sysfs()).unwrap();
+        let slots = enclosures[0].slots().unwrap();
+        let components: Vec<(usize, &str)> = slots
+            .iter()
+            .map(|(id, slot)| (*id, slot.component()))
+            .collect();
+        assert_eq!(
+            components,
+            [
+                (1, "Slot 1"),
+                (2, "Slot 2"),
+                (3, "Front Bay Left"),
+                (4, "Disk004"),
+                (5, "Rear Bay")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_slot_target() {
+        let fixture = SysfsFixture::new("enclosure-target");
+        fixture
+            .file("class/enclosure/0:0:15:0/Slot00/type", "array device")
+            .dir("class/enclosure/0:0:15:0/Slot00/device/block/sdb")
+            .file("class/enclosure/0:0:15:0/Slot01/type", "array device")
+            .dir("class/enclosure/0:0:15:0/Slot01/device/block/sdc")
+            .file(
+                "class/enclosure/0:0:15:0/Slot01/device/wwid",
+                "naa.5000c500aabbccdd",
+            )
+            .file("class/enclosure/0:0:15:0/Slot01/locate", "0")
+            .file("devices/host0/block/sdc/sdc1/partition", "1")
+            .symlink("class/block/sdc1", "../../devices/host0/block/sdc/sdc1")
+            .host_file("dev/sdc", "")
+            .host_file("dev/sdc1", "")
+            .host_symlink("dev/disk/by-id/wwn-0x5000c500aabbccdd-part1", "../../sdc1")
+            .host_symlink("dev/disk/by-id/ata-ST4000NM0033_Z1Z0ABCD", "../../sdc");
+        let enclosures = Enclosure::load_all(&fixture.sysfs()).unwrap();
+        let resolver = BlockResolver::from_sysfs(&fixture.sysfs());
+
+        for target in [
+            "0:0:15:0/1",
+            "sdc",
+            "/dev/sdc",
+            "wwn-0x5000C500AABBCCDD",
+            "/dev/disk/by-id/ata-ST4000NM0033_Z1Z0ABCD",
+            // no such link, matched by the wwn in its name
+            "/dev/disk/by-id/wwn-0x5000c500aabbccdd",
+            // partitions are on the whole disk in the slot
+            "sdc1",
+            "/dev/sdc1",
+            "/dev/disk/by-id/wwn-0x5000c500aabbccdd-part1",
+        ] {
+            let slot = SlotTarget::parse(target, &resolver)
+                .find(&enclosures)
+                .unwrap();
+            assert_eq!(slot.slot_id(), 1, "{}", target);
+        }
+        assert!(SlotTarget::parse("sdz", &resolver)
+            .find(&enclosures)
+            .is_err());
+
+        let slot = SlotTarget::parse("sdc", &resolver)
+            .find(&enclosures)
+            .unwrap();
+        slot.set_locate(true).unwrap();
+        assert!(slot.is_locating());
+        slot.set_locate(false).unwrap();
+        assert!(!slot.is_locating());
+
+        let guard = LocateGuard::new(&slot).unwrap();
+        assert!(slot.is_locating());
+        guard.restore().unwrap();
+        assert!(!slot.is_locating());
+
+        // already on before, stays on
+        slot.set_locate(true).unwrap();
+        drop(LocateGuard::new(&slot).unwrap());
+        assert!(slot.is_locating());
+
+        // no fault attribute, and nothing created in its place
+        assert!(matches!(
+            slot.set_fault(true),
+            Err(SError::UnsupportedAttribute { .. })
+        ));
+        assert!(!fixture
+            .sysfs()
+            .enclosure_dir()
+            .join("0:0:15:0/Slot01/fault")
+            .exists());
+    }
+
+    #[test]
+    fn test_trailing_number() {
+        assert_eq!(trailing_number("Slot00"), Some(0));
+        assert_eq!(trailing_number("ArrayDevice12"), Some(12));
+        assert_eq!(trailing_number("7"), Some(7));
+        assert_eq!(trailing_number("Front"), None);
+    }
+
+    #[test]
+    fn test_no_enclosures() {
+        let fixture = SysfsFixture::new("enclosure-empty");
+        fixture.dir("class/enclosure");
+        assert!(Enclosure::load_all(&fixture.sysfs()).is_err());
     }
 }
@@ -19,10 +19,24 @@ pub enum SError {
     },
     #[error("NoEnclosuresFound")]
     NoEnclosuresFound,
-    #[error("MoreThanOneEnclosureFound")]
-    MoreThanOneEnclosureFound,
-    #[error("ComponentsNaN {path}")]
-    ComponentsNaN { path: PathBuf },
+    #[error("SlotNaN {path}")]
+    SlotNaN { path: PathBuf },
+    #[error("PermissionDenied {path}")]
+    PermissionDenied { path: PathBuf },
+    #[error("UnsupportedAttribute {path}")]
+    UnsupportedAttribute { path: PathBuf },
+    #[error("SlotNotFound {target}")]
+    SlotNotFound { target: String },
+    #[error("AmbiguousVdev {name} is in pools {pools}, use pool/{name}")]
+    AmbiguousVdev { name: String, pools: String },
+    #[error("LayoutConfig {path} {message}")]
+    LayoutConfig { path: PathBuf, message: String },
+    #[error("Command {command} {message}")]
+    Command { command: String, message: String },
+    #[error("ZfsOutput {message}")]
+    ZfsOutput { message: String },
+    #[error("LvmOutput {message}")]
+    LvmOutput { message: String },
 }
 
 impl Debug for SError {
@@ -40,6 +54,17 @@ impl SError {
             bt: Backtrace::capture(),
         }
     }
+
+    /// Writing sysfs attributes fails with EACCES when not root, EINVAL when the driver can't set it
+    pub fn attribute_write(path: PathBuf) -> impl FnOnce(io::Error) -> SError {
+        |err| match err.kind() {
+            io::ErrorKind::PermissionDenied => SError::PermissionDenied { path },
+            io::ErrorKind::NotFound | io::ErrorKind::InvalidInput | io::ErrorKind::Unsupported => {
+                SError::UnsupportedAttribute { path }
+            }
+            _ => SError::io(path)(err),
+        }
+    }
 }
 
 pub fn io_op<T>(source: io::Result<T>, path: impl AsRef<Path>) -> SResult<T> {
@@ -0,0 +1,329 @@
+use std::{
+    cell::RefCell,
+    fs::{read_dir, read_to_string},
+    io::ErrorKind,
+    path::Path,
+};
+
+use crate::{
+    blockdev::BlockResolver,
+    err::{io_op, SResult},
+    sysfs::SysfsRoot,
+    utils::{read_to_string_trim, PrivacyNames},
+};
+
+/// Walks `holders/` up from a disk to whatever consumes it
+pub struct Holders {
+    sysfs: SysfsRoot,
+    /// (kernel name, mountpoint)
+    mounts: Vec<(String, String)>,
+    /// Set for `--privacy`, layers are read as they're walked
+    privacy: Option<RefCell<PrivacyNames>>,
+}
+
+impl Holders {
+    pub fn load(sysfs: &SysfsRoot, resolver: &BlockResolver) -> SResult<Self> {
+        let mounts_path = sysfs.proc_dir().join("self").join("mounts");
+        let mounts = match read_to_string(&mounts_path) {
+            Ok(mounts) => mounts,
+            Err(err) if err.kind() == ErrorKind::NotFound => String::new(),
+            Err(err) => return io_op(Err(err), &mounts_path),
+        };
+        Ok(Self::new(sysfs.clone(), &mounts, resolver))
+    }
+
+    /// `/dev/mapper/vg_data-home /home ext4 rw 0 0`, only block devices matter
+    fn new(sysfs: SysfsRoot, mounts: &str, resolver: &BlockResolver) -> Self {
+        let mounts = mounts
+            .lines()
+            .filter_map(|line| {
+                let mut words = line.split_whitespace();
+                let device = words.next()?;
+                let mountpoint = words.next()?;
+                device
+                    .starts_with("/dev/")
+                    .then(|| (resolver.kernel_name(device), mountpoint.to_string()))
+            })
+            .collect();
+        Self {
+            sysfs,
+            mounts,
+            privacy: None,
+        }
+    }
+
+    /// Volume group, md, dm and mount names from `names` from now on
+    pub fn anonymize(&mut self, names: PrivacyNames) {
+        self.privacy = Some(RefCell::new(names));
+    }
+
+    /// The disk, its partitions, and everything holding them
+    pub fn consumer_tree(&self, device: &str) -> Consumer {
+        let class_path = self.sysfs.block_dir().join(device);
+        let mut holders = partitions(&class_path);
+        holders.extend(dir_names(&class_path.join("holders")));
+        let mut layer = Layer::read(device, &class_path);
+        let mut mountpoint = self
+            .mounts
+            .iter()
+            .find(|(name, _)| name == device)
+            .map(|(_, mountpoint)| mountpoint.clone());
+        if let Some(names) = &self.privacy {
+            let mut names = names.borrow_mut();
+            layer = layer.anonymized(&mut names);
+            mountpoint =
+                mountpoint.map(|mountpoint| format!("/{}", names.name("mnt", &mountpoint)));
+        }
+        Consumer {
+            device: device.to_string(),
+            layer,
+            mountpoint,
+            holders: holders
+                .iter()
+                .map(|holder| self.consumer_tree(holder))
+                .collect(),
+        }
+    }
+
+    /// `LUKS→LVM vg_data /home` or a mountpoint, None for an unused disk
+    pub fn top_consumer(&self, device: &str) -> Option<String> {
+        self.consumer_tree(device).describe()
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Consumer {
+    pub device: String,
+    pub layer: Layer,
+    pub mountpoint: Option<String>,
+    pub holders: Vec<Consumer>,
+}
+
+impl Consumer {
+    /// Layers along the longest chain and its mountpoint, `+N` for other branches
+    pub fn describe(&self) -> Option<String> {
+        let chain = self.longest_chain();
+        let mut res = chain
+            .iter()
+            .filter_map(|consumer| consumer.layer.name())
+            .collect::<Vec<String>>()
+            .join("→");
+        if let Some(mountpoint) = chain.last().and_then(|top| top.mountpoint.as_ref()) {
+            if !res.is_empty() {
+                res.push(' ');
+            }
+            res.push_str(mountpoint);
+        }
+        if res.is_empty() {
+            return None;
+        }
+        let others = self.tops() - 1;
+        if others != 0 {
+            res.push_str(&format!(" +{}", others));
+        }
+        Some(res)
+    }
+
+    /// Topmost layer along the longest chain, `mounted` for a filesystem right on the disk.
+    /// Mountpoints aren't keys, every disk would get its own color
+    pub fn group_key(&self) -> Option<String> {
+        let chain = self.longest_chain();
+        chain
+            .iter()
+            .rev()
+            .find_map(|consumer| consumer.layer.name())
+            .or_else(|| {
+                chain
+                    .last()
+                    .and_then(|top| top.mountpoint.as_ref())
+                    .map(|_| "mounted".to_string())
+            })
+    }
+
+    fn longest_chain(&self) -> Vec<&Consumer> {
+        let mut longest = Vec::new();
+        for holder in &self.holders {
+            let chain = holder.longest_chain();
+            if chain.len() > longest.len() {
+                longest = chain;
+            }
+        }
+        longest.insert(0, self);
+        longest
+    }
+
+    /// Consumers with nothing above them that are used for something
+    fn tops(&self) -> usize {
+        if self.holders.is_empty() {
+            let used = self.mountpoint.is_some() || self.layer.name().is_some();
+            return usize::from(used);
+        }
+        self.holders
+            .iter()
+            .map(Consumer::tops)
+            .sum::<usize>()
+            .max(1)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Layer {
+    Disk,
+    Partition,
+    Luks,
+    Multipath,
+    /// `md0`
+    Md(String),
+    /// Logical volume, with the volume group
+    Lvm(String),
+    /// Other device mapper targets by name
+    Dm(String),
+}
+
+impl Layer {
+    /// From the sysfs attributes, dm targets by their `dm/uuid` prefix
+    fn read(device: &str, class_path: &Path) -> Self {
+        if class_path.join("partition").exists() {
+            return Self::Partition;
+        }
+        if class_path.join("md").exists() {
+            return Self::Md(device.to_string());
+        }
+        let dm_dir = class_path.join("dm");
+        if !dm_dir.exists() {
+            return Self::Disk;
+        }
+        let uuid = read_to_string_trim(dm_dir.join("uuid")).unwrap_or_default();
+        let name = read_to_string_trim(dm_dir.join("name")).unwrap_or(device.to_string());
+        if uuid.starts_with("CRYPT-LUKS") {
+            Self::Luks
+        } else if uuid.starts_with("mpath-") {
+            Self::Multipath
+        } else if uuid.starts_with("LVM-") {
+            Self::Lvm(vg_from_dm_name(&name))
+        } else {
+            Self::Dm(name)
+        }
+    }
+
+    fn anonymized(self, names: &mut PrivacyNames) -> Self {
+        match self {
+            Self::Md(name) => Self::Md(names.name("md", &name)),
+            Self::Lvm(vg_name) => Self::Lvm(names.name("vg", &vg_name)),
+            Self::Dm(name) => Self::Dm(names.name("dm", &name)),
+            other => other,
+        }
+    }
+
+    /// None for the disk and partitions, they aren't consumers
+    pub fn name(&self) -> Option<String> {
+        match self {
+            Self::Disk | Self::Partition => None,
+            Self::Luks => Some("LUKS".into()),
+            Self::Multipath => Some("mpath".into()),
+            Self::Md(name) => Some(format!("MD {}", name)),
+            Self::Lvm(vg_name) => Some(format!("LVM {}", vg_name)),
+            Self::Dm(name) => Some(format!("dm {}", name)),
+        }
+    }
+}
+
+/// `vg_data-lv--home` is vg `vg_data`, dashes in names are doubled
+fn vg_from_dm_name(name: &str) -> String {
+    let mut vg_name = String::new();
+    let mut chars = name.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '-' && chars.next_if_eq(&'-').is_none() {
+            break;
+        }
+        vg_name.push(c);
+    }
+    vg_name
+}
+
+/// `sdb1` dirs under `sdb` that are partitions
+fn partitions(class_path: &Path) -> Vec<String> {
+    let mut names = dir_names(class_path);
+    names.retain(|name| class_path.join(name).join("partition").exists());
+    names
+}
+
+fn dir_names(dir: &Path) -> Vec<String> {
+    let Ok(entries) = read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = entries
+        .flatten()
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .collect();
+    names.sort();
+    names
+}
+
+#[cfg(test)]
+mod test {
+    use super::{vg_from_dm_name, Holders};
+    use crate::{blockdev::BlockResolver, sysfs::fixture::SysfsFixture, utils::PrivacyNames};
+
+    #[test]
+    fn test_top_consumer() {
+        let fixture = SysfsFixture::new("holders");
+        fixture
+            .file("devices/host0/block/sdb/sdb1/partition", "1")
+            .dir("devices/host0/block/sdb/sdb1/holders/dm-0")
+            .file(
+                "devices/virtual/block/dm-0/dm/uuid",
+                "CRYPT-LUKS2-3f1c-luks-3f1c",
+            )
+            .file("devices/virtual/block/dm-0/dm/name", "luks-3f1c")
+            .dir("devices/virtual/block/dm-0/holders/dm-1")
+            .dir("devices/virtual/block/dm-0/holders/dm-2")
+            .file("devices/virtual/block/dm-1/dm/uuid", "LVM-abc")
+            .file("devices/virtual/block/dm-1/dm/name", "vg_data-lv--home")
+            .file("devices/virtual/block/dm-2/dm/uuid", "LVM-def")
+            .file("devices/virtual/block/dm-2/dm/name", "vg_data-swap")
+            .dir("devices/host0/block/sdc")
+            .dir("devices/host0/block/sdd")
+            .symlink("class/block/sdb", "../../devices/host0/block/sdb")
+            .symlink("class/block/sdb1", "../../devices/host0/block/sdb/sdb1")
+            .symlink("class/block/sdc", "../../devices/host0/block/sdc")
+            .symlink("class/block/sdd", "../../devices/host0/block/sdd")
+            .symlink("class/block/dm-0", "../../devices/virtual/block/dm-0")
+            .symlink("class/block/dm-1", "../../devices/virtual/block/dm-1")
+            .symlink("class/block/dm-2", "../../devices/virtual/block/dm-2")
+            .host_file("dev/dm-1", "")
+            .host_symlink("dev/mapper/vg_data-lv--home", "../dm-1")
+            .host_file(
+                "proc/self/mounts",
+                "proc /proc proc rw 0 0
+/dev/mapper/vg_data-lv--home /home ext4 rw 0 0
+/dev/sdc /srv xfs rw 0 0
+",
+            );
+        let sysfs = fixture.sysfs();
+        let mut holders = Holders::load(&sysfs, &BlockResolver::from_sysfs(&sysfs)).unwrap();
+
+        assert_eq!(
+            holders.top_consumer("sdb").as_deref(),
+            Some("LUKS→LVM vg_data /home +1")
+        );
+        assert_eq!(
+            holders.consumer_tree("sdb").group_key().as_deref(),
+            Some("LVM vg_data")
+        );
+        assert_eq!(holders.top_consumer("sdc").as_deref(), Some("/srv"));
+        assert_eq!(
+            holders.consumer_tree("sdc").group_key().as_deref(),
+            Some("mounted")
+        );
+        assert_eq!(holders.top_consumer("sdd"), None);
+        assert_eq!(holders.consumer_tree("sdd").group_key(), None);
+        assert_eq!(vg_from_dm_name("vg--fast-lv"), "vg-fast");
+
+        holders.anonymize(PrivacyNames::default());
+        assert_eq!(
+            holders.top_consumer("sdb").as_deref(),
+            Some("LUKS→LVM vg0 /mnt0 +1")
+        );
+    }
+}
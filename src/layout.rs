@@ -0,0 +1,598 @@
+use std::{
+    collections::HashMap,
+    fs::read_to_string,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use serde::Deserialize;
+
+use crate::{
+    console_widget::SlotPrintOrder,
+    enclosure::Enclosure,
+    err::{SError, SResult},
+};
+
+pub const DEFAULT_LAYOUT_CONFIG: &str = "/etc/shelf-viewer/layouts.toml";
+
+/// Physical arrangement of an enclosure's slots
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChassisLayout {
+    pub name: String,
+    /// Front first then rear. Slots fill the panels in slot number order
+    pub panels: Vec<PanelLayout>,
+    /// Printed instead of the slot number
+    pub labels: HashMap<usize, String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PanelLayout {
+    pub name: String,
+    pub rows: usize,
+    pub columns: usize,
+    pub order: SlotPrintOrder,
+    /// Positions in numbering order without a slot, eg PSUs or fan modules
+    pub gaps: Vec<usize>,
+    /// Slots in each row from the top when they differ, eg `[12, 12, 12, 12, 12, 2]`
+    pub row_lengths: Vec<usize>,
+    /// Slots come from this enclosure instead, eg a rear backplane with its own expander
+    pub enclosure: Option<String>,
+    pub view: PanelView,
+}
+
+/// How a panel is drawn
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum PanelView {
+    /// Bays seen from the front or rear, one big cell per slot
+    #[default]
+    Bays,
+    /// Top loader drawer seen from above with rows running front to back.
+    /// Compact cells labeled by row letter and column number
+    Drawer,
+    /// Bays standing on end like 2U 24x 2.5", tall narrow cells with the text stacked
+    Vertical,
+}
+
+impl FromStr for PanelView {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "bays" => Ok(Self::Bays),
+            "drawer" => Ok(Self::Drawer),
+            "vertical" => Ok(Self::Vertical),
+            _ => Err(format!("unknown panel view {}", value)),
+        }
+    }
+}
+
+impl PanelLayout {
+    /// Enough rows of `columns` for every slot, like before layouts existed
+    pub fn with_width(total_slots: usize, columns: usize, order: SlotPrintOrder) -> Self {
+        Self {
+            name: "front".into(),
+            rows: total_slots.div_ceil(columns),
+            columns,
+            order,
+            gaps: Vec::new(),
+            row_lengths: Vec::new(),
+            enclosure: None,
+            view: PanelView::Bays,
+        }
+    }
+
+    pub fn row_lengths(&self) -> Vec<usize> {
+        if self.row_lengths.is_empty() {
+            vec![self.columns; self.rows]
+        } else {
+            self.row_lengths.clone()
+        }
+    }
+
+    pub fn widest_row(&self) -> usize {
+        self.row_lengths().into_iter().max().unwrap_or(0)
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.row_lengths().iter().sum::<usize>() - self.gaps.len()
+    }
+
+    /// Slot index for each of the `rows` x `columns` cells, see [SlotPrintOrder::grid]
+    pub fn grid(&self, total_slots: usize) -> Vec<Option<usize>> {
+        self.order
+            .grid(&self.row_lengths(), &self.gaps, total_slots)
+    }
+}
+
+/// User layouts for shelves the built-in table doesn't know
+///
+/// ```toml
+/// [[layout]]
+/// name = "Backup shelf"
+/// id = "0x500605b000000001" # logical id or enc_id, or match by vendor/model/slots
+/// rows = 4
+/// columns = 6
+/// order = "bottom-left-going-up"
+/// gaps = [23]
+/// labels = { 0 = "A1", 1 = "A2" }
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LayoutConfig {
+    #[serde(skip)]
+    path: PathBuf,
+    #[serde(default)]
+    layout: Vec<LayoutConfigEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct LayoutConfigEntry {
+    name: Option<String>,
+    id: Option<String>,
+    vendor: Option<String>,
+    model: Option<String>,
+    slots: Option<usize>,
+    rows: Option<usize>,
+    columns: Option<usize>,
+    order: Option<String>,
+    #[serde(default)]
+    gaps: Vec<usize>,
+    #[serde(default)]
+    row_lengths: Vec<usize>,
+    /// `bays`, `drawer` or `vertical`
+    view: Option<String>,
+    #[serde(default)]
+    labels: HashMap<String, String>,
+    /// Instead of rows/columns/order for front and rear
+    #[serde(default)]
+    panels: Vec<PanelConfigEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PanelConfigEntry {
+    name: String,
+    rows: usize,
+    columns: usize,
+    order: Option<String>,
+    #[serde(default)]
+    gaps: Vec<usize>,
+    #[serde(default)]
+    row_lengths: Vec<usize>,
+    /// enc_id or logical id when the panel is exposed as its own enclosure
+    enclosure: Option<String>,
+    view: Option<String>,
+}
+
+impl LayoutConfig {
+    /// A missing file is an empty config
+    pub fn load(path: impl AsRef<Path>) -> SResult<Self> {
+        let path = path.as_ref();
+        let content = match read_to_string(path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(Self::default()),
+            Err(err) => return Err(SError::io(path.to_path_buf())(err)),
+        };
+        let mut config: Self = toml::from_str(&content).map_err(|err| SError::LayoutConfig {
+            path: path.to_path_buf(),
+            message: err.message().to_string(),
+        })?;
+        config.path = path.to_path_buf();
+        // fail early rather than when the shelf is drawn
+        for entry in &config.layout {
+            entry.to_layout(path)?;
+        }
+        Ok(config)
+    }
+
+    /// First entry matching the enclosure's id, then by vendor/model
+    pub fn find(
+        &self,
+        enclosure: &Enclosure,
+        vendor: &str,
+        model: &str,
+        slots: usize,
+    ) -> SResult<Option<ChassisLayout>> {
+        let by_id = self
+            .layout
+            .iter()
+            .find(|entry| entry.id.as_ref().is_some_and(|id| enclosure.has_id(id)));
+        let by_model = || {
+            self.layout.iter().find(|entry| {
+                entry.id.is_none()
+                    && (entry.vendor.is_some() || entry.model.is_some())
+                    && entry
+                        .vendor
+                        .as_ref()
+                        .is_none_or(|v| vendor.eq_ignore_ascii_case(v))
+                    && entry
+                        .model
+                        .as_ref()
+                        .is_none_or(|m| model.eq_ignore_ascii_case(m))
+                    && entry.slots.is_none_or(|s| s == slots)
+            })
+        };
+        by_id
+            .or_else(by_model)
+            .map(|entry| entry.to_layout(&self.path))
+            .transpose()
+    }
+}
+
+impl LayoutConfigEntry {
+    fn to_layout(&self, path: &Path) -> SResult<ChassisLayout> {
+        let invalid = |message: String| SError::LayoutConfig {
+            path: path.to_path_buf(),
+            message,
+        };
+        let name = self
+            .name
+            .clone()
+            .or(self.id.clone())
+            .or(self.model.clone())
+            .unwrap_or("custom".into());
+        let parse_order = |order: &Option<String>| match order {
+            Some(order) => order.parse().map_err(invalid),
+            None => Ok(SlotPrintOrder::BOTTOM_LEFT_GOING_UP),
+        };
+        let parse_view = |view: &Option<String>| match view.as_ref().or(self.view.as_ref()) {
+            Some(view) => view.parse().map_err(invalid),
+            None => Ok(PanelView::Bays),
+        };
+
+        let panels = if self.panels.is_empty() {
+            let (Some(rows), Some(columns)) = (self.rows, self.columns) else {
+                return Err(invalid(format!(
+                    "{} needs rows and columns or panels",
+                    name
+                )));
+            };
+            vec![PanelLayout {
+                name: "front".into(),
+                rows,
+                columns,
+                order: parse_order(&self.order)?,
+                gaps: self.gaps.clone(),
+                row_lengths: self.row_lengths.clone(),
+                enclosure: None,
+                view: parse_view(&None)?,
+            }]
+        } else {
+            self.panels
+                .iter()
+                .map(|panel| {
+                    Ok(PanelLayout {
+                        name: panel.name.clone(),
+                        rows: panel.rows,
+                        columns: panel.columns,
+                        order: parse_order(&panel.order)?,
+                        gaps: panel.gaps.clone(),
+                        row_lengths: panel.row_lengths.clone(),
+                        enclosure: panel.enclosure.clone(),
+                        view: parse_view(&panel.view)?,
+                    })
+                })
+                .collect::<SResult<Vec<PanelLayout>>>()?
+        };
+        for panel in &panels {
+            if panel.rows == 0 || panel.columns == 0 {
+                return Err(invalid(format!(
+                    "{} {} needs at least one row and column",
+                    name, panel.name
+                )));
+            }
+            if panel.row_lengths.contains(&0) {
+                return Err(invalid(format!(
+                    "{} {} row_lengths can't have empty rows",
+                    name, panel.name
+                )));
+            }
+            if !panel.row_lengths.is_empty()
+                && (panel.row_lengths.len() != panel.rows
+                    || panel.row_lengths.iter().any(|len| *len > panel.columns))
+            {
+                return Err(invalid(format!(
+                    "{} {} row_lengths must have {} rows of at most {} columns",
+                    name, panel.name, panel.rows, panel.columns
+                )));
+            }
+            let positions = panel.row_lengths().iter().sum::<usize>();
+            let mut gaps = panel.gaps.clone();
+            gaps.sort();
+            gaps.dedup();
+            if gaps.len() != panel.gaps.len() || gaps.last().is_some_and(|gap| *gap >= positions) {
+                return Err(invalid(format!(
+                    "{} {} gaps must be distinct positions below {}",
+                    name, panel.name, positions
+                )));
+            }
+        }
+
+        let labels = self
+            .labels
+            .iter()
+            .map(|(slot, label)| {
+                let slot = slot
+                    .parse()
+                    .map_err(|_| invalid(format!("label key {} is not a slot number", slot)))?;
+                Ok((slot, label.clone()))
+            })
+            .collect::<SResult<HashMap<usize, String>>>()?;
+
+        Ok(ChassisLayout {
+            name,
+            panels,
+            labels,
+        })
+    }
+}
+
+struct BuiltinLayout {
+    name: &'static str,
+    /// Case insensitive prefix of `device/vendor`
+    vendors: &'static [&'static str],
+    /// Case insensitive substring of `device/model`
+    models: &'static [&'static str],
+    /// Expander chips are shared between chassis, tell them apart by bay count
+    slots: Option<usize>,
+    /// rows, columns, order. The first drawer for top loaders
+    front: (usize, usize, SlotPrintOrder),
+    /// The second drawer for top loaders
+    rear: Option<(usize, usize, SlotPrintOrder)>,
+    view: PanelView,
+}
+
+// Supermicro backplanes report their LSI expander instead of the chassis
+const SUPERMICRO_VENDORS: &[&str] = &["Supermicro", "SMC", "LSI"];
+
+const BUILTIN_LAYOUTS: &[BuiltinLayout] = &[
+    BuiltinLayout {
+        name: "Supermicro 847",
+        vendors: SUPERMICRO_VENDORS,
+        models: &["847", "SAS2X36", "SAS3x40"],
+        slots: Some(36),
+        front: (4, 6, SlotPrintOrder::BOTTOM_LEFT_GOING_UP),
+        rear: Some((2, 6, SlotPrintOrder::BOTTOM_LEFT_GOING_UP)),
+        view: PanelView::Bays,
+    },
+    BuiltinLayout {
+        name: "Supermicro 846",
+        vendors: SUPERMICRO_VENDORS,
+        models: &["846", "SAS2X36", "SAS3x40"],
+        slots: Some(24),
+        front: (4, 6, SlotPrintOrder::BOTTOM_LEFT_GOING_UP),
+        rear: None,
+        view: PanelView::Bays,
+    },
+    BuiltinLayout {
+        name: "Supermicro 826",
+        vendors: SUPERMICRO_VENDORS,
+        models: &["826", "SAS2X28", "SAS3x28"],
+        slots: Some(12),
+        front: (3, 4, SlotPrintOrder::BOTTOM_LEFT_GOING_UP),
+        rear: None,
+        view: PanelView::Bays,
+    },
+    BuiltinLayout {
+        name: "NetApp DS4246",
+        vendors: &["NETAPP"],
+        models: &["DS424"],
+        slots: None,
+        front: (4, 6, SlotPrintOrder::TOP_LEFT_GOING_RIGHT),
+        rear: None,
+        view: PanelView::Bays,
+    },
+    BuiltinLayout {
+        name: "HGST 4U60",
+        vendors: &["HGST", "WDC"],
+        models: &["H4060", "4U60"],
+        slots: None,
+        front: (5, 12, SlotPrintOrder::TOP_LEFT_GOING_DOWN),
+        rear: None,
+        view: PanelView::Drawer,
+    },
+    BuiltinLayout {
+        name: "Seagate 5U84",
+        vendors: &["SEAGATE"],
+        models: &["5U84"],
+        slots: Some(84),
+        front: (3, 14, SlotPrintOrder::TOP_LEFT_GOING_RIGHT),
+        rear: Some((3, 14, SlotPrintOrder::TOP_LEFT_GOING_RIGHT)),
+        view: PanelView::Drawer,
+    },
+    BuiltinLayout {
+        name: "Dell MD1200",
+        vendors: &["DELL"],
+        models: &["MD1200"],
+        slots: None,
+        front: (3, 4, SlotPrintOrder::TOP_LEFT_GOING_RIGHT),
+        rear: None,
+        view: PanelView::Bays,
+    },
+    BuiltinLayout {
+        name: "Dell MD1400",
+        vendors: &["DELL"],
+        models: &["MD1400"],
+        slots: None,
+        front: (3, 4, SlotPrintOrder::TOP_LEFT_GOING_RIGHT),
+        rear: None,
+        view: PanelView::Bays,
+    },
+];
+
+/// Known chassis by the enclosure's `device/vendor` and `device/model`
+pub fn builtin_layout(vendor: &str, model: &str, slots: usize) -> Option<ChassisLayout> {
+    let vendor = vendor.to_ascii_lowercase();
+    let model = model.to_ascii_lowercase();
+    let builtin = BUILTIN_LAYOUTS.iter().find(|builtin| {
+        builtin
+            .vendors
+            .iter()
+            .any(|v| vendor.starts_with(&v.to_ascii_lowercase()))
+            && builtin
+                .models
+                .iter()
+                .any(|m| model.contains(&m.to_ascii_lowercase()))
+            && builtin
+                .slots
+                .is_none_or(|builtin_slots| builtin_slots == slots)
+    })?;
+
+    let (front, rear) = match builtin.view {
+        PanelView::Bays | PanelView::Vertical => ("front", "rear"),
+        PanelView::Drawer => ("drawer 1", "drawer 2"),
+    };
+    let mut panels = vec![panel(front, builtin.front, builtin.view)];
+    if let Some(rear_layout) = builtin.rear {
+        panels.push(panel(rear, rear_layout, builtin.view));
+    }
+    Some(ChassisLayout {
+        name: builtin.name.to_string(),
+        panels,
+        labels: HashMap::new(),
+    })
+}
+
+fn panel(
+    name: &str,
+    (rows, columns, order): (usize, usize, SlotPrintOrder),
+    view: PanelView,
+) -> PanelLayout {
+    PanelLayout {
+        name: name.to_string(),
+        rows,
+        columns,
+        order,
+        gaps: Vec::new(),
+        row_lengths: Vec::new(),
+        enclosure: None,
+        view,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{builtin_layout, LayoutConfig, PanelView};
+    use crate::{
+        console_widget::SlotPrintOrder, enclosure::Enclosure, sysfs::fixture::SysfsFixture,
+    };
+
+    #[test]
+    fn test_builtin_layout() {
+        let layout = builtin_layout("LSI", "SAS2X36", 24).unwrap();
+        assert_eq!(layout.name, "Supermicro 846");
+        assert_eq!(layout.panels[0].capacity(), 24);
+
+        let layout = builtin_layout("LSI", "SAS2X36", 36).unwrap();
+        assert_eq!(layout.name, "Supermicro 847");
+        assert_eq!(layout.panels.len(), 2);
+
+        let layout = builtin_layout("SEAGATE", "5U84", 84).unwrap();
+        assert_eq!(layout.panels[1].name, "drawer 2");
+        assert_eq!(layout.panels[1].view, PanelView::Drawer);
+
+        let layout = builtin_layout("NETAPP  ", "DS424IOM6", 24).unwrap();
+        assert_eq!(layout.name, "NetApp DS4246");
+
+        assert_eq!(builtin_layout("LSI", "SAS2X36", 45), None);
+        assert_eq!(builtin_layout("ACME", "Shelf", 24), None);
+    }
+
+    #[test]
+    fn test_layout_config() {
+        let fixture = SysfsFixture::new("layout-config");
+        fixture
+            .file("class/enclosure/0:0:15:0/id", "0x500605b000000001")
+            .file("class/enclosure/1:0:15:0/id", "0x500605b000000002")
+            .file(
+                "layouts.toml",
+                r#"
+                [[layout]]
+                name = "Backup shelf"
+                id = "0x500605b000000001"
+                rows = 2
+                columns = 3
+                order = "top-left-going-down"
+                gaps = [5]
+                labels = { 0 = "A1" }
+
+                [[layout]]
+                model = "SAS2X36"
+                slots = 36
+                [[layout.panels]]
+                name = "front"
+                rows = 4
+                columns = 6
+                [[layout.panels]]
+                name = "rear"
+                rows = 2
+                columns = 6
+                enclosure = "2:0:15:0"
+                "#,
+            );
+        let config = LayoutConfig::load(fixture.sysfs().root().join("layouts.toml")).unwrap();
+        let enclosures = Enclosure::load_all(&fixture.sysfs()).unwrap();
+
+        let layout = config
+            .find(&enclosures[0], "LSI", "SAS2X36", 5)
+            .unwrap()
+            .unwrap();
+        assert_eq!(layout.name, "Backup shelf");
+        assert_eq!(layout.panels[0].order, SlotPrintOrder::TOP_LEFT_GOING_DOWN);
+        assert_eq!(layout.panels[0].capacity(), 5);
+        assert_eq!(layout.labels[&0], "A1");
+
+        let layout = config
+            .find(&enclosures[0], "LSI", "SAS2X36", 36)
+            .unwrap()
+            .unwrap();
+        assert_eq!(layout.name, "Backup shelf", "id entry wins over model");
+
+        let layout = config
+            .find(&enclosures[1], "LSI", "SAS2X36", 36)
+            .unwrap()
+            .unwrap();
+        assert_eq!(layout.name, "SAS2X36");
+        assert_eq!(layout.panels.len(), 2);
+        assert_eq!(layout.panels[1].enclosure.as_deref(), Some("2:0:15:0"));
+        assert_eq!(
+            config.find(&enclosures[1], "LSI", "SAS2X36", 24).unwrap(),
+            None
+        );
+
+        let missing = LayoutConfig::load(fixture.sysfs().root().join("missing.toml")).unwrap();
+        assert_eq!(
+            missing.find(&enclosures[0], "LSI", "SAS2X36", 36).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_invalid_layout_config() {
+        let fixture = SysfsFixture::new("layout-invalid");
+
+        for (entry, message) in [
+            ("rows = 0\ncolumns = 4", "needs at least one row and column"),
+            ("rows = 2\ncolumns = 0", "needs at least one row and column"),
+            ("rows = 2\ncolumns = 4\ngaps = [8]", "gaps must be distinct"),
+            (
+                "rows = 2\ncolumns = 4\ngaps = [1, 1]",
+                "gaps must be distinct",
+            ),
+            (
+                "rows = 2\ncolumns = 4\nrow_lengths = [4, 0]",
+                "can't have empty rows",
+            ),
+        ] {
+            let path = fixture.sysfs().root().join("layouts.toml");
+            fixture.file(
+                "layouts.toml",
+                &format!("[[layout]]\nid = \"0x500605b000000001\"\n{}\n", entry),
+            );
+            let err = LayoutConfig::load(&path).unwrap_err();
+            assert!(err.to_string().contains(message), "{}: {}", entry, err);
+        }
+    }
+}
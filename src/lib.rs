@@ -1,14 +1,19 @@
-#![feature(iter_chain)]
 #![feature(error_generic_member_access)]
-#![feature(anonymous_lifetime_in_impl_trait)]
 
 use num_format::Locale;
 
+pub mod blockdev;
+pub mod btrfs;
 pub mod colors;
 pub mod console_widget;
 pub mod enclosure;
 pub mod err;
+pub mod holders;
+pub mod layout;
 pub mod lsblk;
+pub mod lvm;
+pub mod md;
+pub mod sysfs;
 pub mod utils;
 pub mod zfs;
 
@@ -6,7 +6,7 @@ impl Lsblk {
     pub fn execute() -> Vec<LsblkEntry> {
         let res = execute_command(
             "lsblk",
-            &[
+            [
                 "-b", // byte sizes
                 "-d", // devices only not partitions
                 "-r", // scripting mode
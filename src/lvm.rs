@@ -0,0 +1,142 @@
+use serde::Deserialize;
+
+use crate::{
+    blockdev::{is_on_disk, BlockResolver},
+    err::{SError, SResult},
+    utils::try_execute_command_with_stderr,
+};
+
+/// LVM physical volumes and the volume group owning them
+#[derive(Debug, Default, PartialEq)]
+pub struct LvmList {
+    pub pvs: Vec<LvmPv>,
+}
+
+impl LvmList {
+    pub fn execute(resolver: &BlockResolver) -> SResult<Self> {
+        // warnings like `WARNING: Device /dev/sdx has size of 0 sectors` when it still worked
+        let (res, stderr) = try_execute_command_with_stderr(
+            "pvs",
+            [
+                "--reportformat",
+                "json", //
+                "-o",
+                "pv_name,vg_name",
+            ],
+        )?;
+        for warning in stderr.lines() {
+            println!("[E] pvs {}", warning.trim());
+        }
+        let mut lvm_list = Self::parse_pvs(&res)?;
+        lvm_list.resolve_disks(resolver);
+        Ok(lvm_list)
+    }
+
+    fn resolve_disks(&mut self, resolver: &BlockResolver) {
+        for pv in &mut self.pvs {
+            pv.disks = resolver.whole_disks(&pv.pv_name);
+        }
+    }
+
+    /// `{"report": [{"pv": [{"pv_name": "/dev/sdb1", "vg_name": "vg0"}]}]}`
+    fn parse_pvs(res: &str) -> SResult<Self> {
+        let pvs: PvsJson = serde_json::from_str(res).map_err(|err| SError::LvmOutput {
+            message: format!("pvs {}", err),
+        })?;
+        let pvs = pvs
+            .report
+            .into_iter()
+            .flat_map(|report| report.pv)
+            .map(|pv| LvmPv {
+                pv_name: pv.pv_name,
+                // orphan PVs
+                vg_name: Some(pv.vg_name).filter(|vg_name| !vg_name.is_empty()),
+                disks: Vec::new(),
+            })
+            .collect();
+        Ok(Self { pvs })
+    }
+
+    /// By PV name or the whole disk under it
+    pub fn find_pv(&self, device: &str) -> Option<&LvmPv> {
+        self.pvs
+            .iter()
+            .find(|pv| is_on_disk(&pv.pv_name, &pv.disks, device))
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct LvmPv {
+    /// `/dev/sdb1`, `/dev/mapper/luks-...`, or `[unknown]` when missing
+    pub pv_name: String,
+    pub vg_name: Option<String>,
+    /// Whole disks under a partition, dm-crypt or multipath device
+    pub disks: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct PvsJson {
+    report: Vec<PvsReportJson>,
+}
+
+#[derive(Deserialize)]
+struct PvsReportJson {
+    #[serde(default)]
+    pv: Vec<PvJson>,
+}
+
+#[derive(Deserialize)]
+struct PvJson {
+    pv_name: String,
+    #[serde(default)]
+    vg_name: String,
+}
+
+#[cfg(test)]
+mod test {
+    use super::LvmList;
+    use crate::{blockdev::BlockResolver, sysfs::fixture::SysfsFixture};
+
+    const PVS_JSON: &str = r#"  {
+      "report": [
+          {
+              "pv": [
+                  {"pv_name":"/dev/sdb1", "vg_name":"vg0"},
+                  {"pv_name":"/dev/mapper/luks-3f1c", "vg_name":"vg0"},
+                  {"pv_name":"/dev/sdd", "vg_name":""}
+              ]
+          }
+      ]
+  }
+"#;
+
+    #[test]
+    fn test_pvs() {
+        let fixture = SysfsFixture::new("lvm");
+        fixture
+            .file("devices/host0/block/sdb/sdb1/partition", "1")
+            .file("devices/host0/block/sdc/sdc2/partition", "2")
+            .dir("devices/virtual/block/dm-0/slaves/sdc2")
+            .symlink("class/block/sdb1", "../../devices/host0/block/sdb/sdb1")
+            .symlink("class/block/sdc2", "../../devices/host0/block/sdc/sdc2")
+            .symlink("class/block/dm-0", "../../devices/virtual/block/dm-0")
+            .host_file("dev/dm-0", "")
+            .host_symlink("dev/mapper/luks-3f1c", "../dm-0");
+        let resolver = BlockResolver::from_sysfs(&fixture.sysfs());
+
+        let mut lvm_list = LvmList::parse_pvs(PVS_JSON).unwrap();
+        lvm_list.resolve_disks(&resolver);
+        assert_eq!(lvm_list.pvs.len(), 3);
+        assert_eq!(
+            lvm_list.find_pv("sdb").unwrap().vg_name.as_deref(),
+            Some("vg0")
+        );
+        // dm-crypt on a partition
+        assert_eq!(
+            lvm_list.find_pv("sdc").unwrap().pv_name,
+            "/dev/mapper/luks-3f1c"
+        );
+        assert_eq!(lvm_list.find_pv("sdd").unwrap().vg_name, None);
+        assert!(LvmList::parse_pvs("{\"report\": {}}").is_err());
+    }
+}
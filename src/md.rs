@@ -0,0 +1,309 @@
+use std::{fs::read_to_string, io::ErrorKind};
+
+use crate::{
+    blockdev::{is_on_disk, BlockResolver},
+    err::{io_op, SResult},
+    sysfs::SysfsRoot,
+    utils::read_to_string_trim,
+};
+
+/// Linux software RAID arrays from mdadm
+#[derive(Debug, Default, PartialEq)]
+pub struct MdList {
+    pub arrays: Vec<MdArray>,
+}
+
+impl MdList {
+    /// Empty without the md driver loaded
+    pub fn load(sysfs: &SysfsRoot, resolver: &BlockResolver) -> SResult<Self> {
+        let mdstat_path = sysfs.proc_dir().join("mdstat");
+        let mdstat = match read_to_string(&mdstat_path) {
+            Ok(mdstat) => mdstat,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(Self::default()),
+            Err(err) => return io_op(Err(err), &mdstat_path),
+        };
+        let mut md_list = Self::parse_mdstat(&mdstat);
+        md_list.read_member_states(sysfs);
+        md_list.resolve_disks(resolver);
+        Ok(md_list)
+    }
+
+    /// `md0 : active raid1 sdb1[1] sda1[0](F)` then indented status lines until a blank line
+    fn parse_mdstat(res: &str) -> Self {
+        let mut md_list = Self::default();
+        for line in res.lines() {
+            let trimmed = line.trim();
+            if let Some((name, rest)) = trimmed.split_once(" : ") {
+                if name == "Personalities" || !name.starts_with("md") {
+                    continue;
+                }
+                md_list.arrays.push(MdArray::from_mdstat_line(name, rest));
+            } else if let Some(array) = md_list.arrays.last_mut() {
+                if line.starts_with(' ') {
+                    if let Some(sync) = MdSync::from_mdstat_line(trimmed) {
+                        array.sync = Some(sync);
+                    }
+                }
+            }
+        }
+        md_list
+    }
+
+    /// `md/dev-sdb1/state` is more exact than the mdstat flags, eg a disk being rebuilt is a spare
+    fn read_member_states(&mut self, sysfs: &SysfsRoot) {
+        for array in &mut self.arrays {
+            let md_dir = sysfs.block_dir().join(&array.name).join("md");
+            for member in &mut array.members {
+                let path = md_dir.join(format!("dev-{}", member.device)).join("state");
+                if let Ok(state) = read_to_string_trim(path) {
+                    let role = MdRole::from_state(&state);
+                    // mdstat only marks real spares with (S)
+                    member.rebuilding = role == MdRole::Spare && member.role != MdRole::Spare;
+                    member.role = role;
+                }
+            }
+        }
+    }
+
+    fn resolve_disks(&mut self, resolver: &BlockResolver) {
+        for array in &mut self.arrays {
+            for member in &mut array.members {
+                member.disks = resolver.whole_disks(&member.device);
+            }
+        }
+    }
+
+    /// By member name or the whole disk under it
+    pub fn find_member(&self, device: &str) -> Option<(&MdArray, &MdMember)> {
+        self.arrays.iter().find_map(|array| {
+            array
+                .members
+                .iter()
+                .find(|member| is_on_disk(&member.device, &member.disks, device))
+                .map(|member| (array, member))
+        })
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct MdArray {
+    /// `md0`
+    pub name: String,
+    /// `active`, `inactive`, or `active (auto-read-only)`
+    pub state: String,
+    /// `raid6`, missing on inactive arrays
+    pub level: Option<String>,
+    pub members: Vec<MdMember>,
+    /// resync, recovery, check or reshape running now
+    pub sync: Option<MdSync>,
+}
+
+impl MdArray {
+    /// `active raid1 sdb1[1] sda1[0](F)`
+    fn from_mdstat_line(name: &str, rest: &str) -> Self {
+        let mut words = rest.split_whitespace().peekable();
+        let mut state = words.next().unwrap_or_default().to_string();
+        if let Some(mode) = words.next_if(|word| word.starts_with('(')) {
+            state.push(' ');
+            state.push_str(mode);
+        }
+        let level = words
+            .next_if(|word| !word.contains('['))
+            .map(str::to_string);
+        let members = words.filter_map(MdMember::from_mdstat_word).collect();
+        Self {
+            name: name.to_string(),
+            state,
+            level,
+            members,
+            sync: None,
+        }
+    }
+
+    /// Recovery only writes the disks being rebuilt, resync, check and reshape read every active one
+    pub fn sync_of(&self, member: &MdMember) -> Option<&MdSync> {
+        let sync = self.sync.as_ref()?;
+        let taking_part = match sync.action.as_str() {
+            "recovery" if self.members.iter().any(|member| member.rebuilding) => member.rebuilding,
+            _ => member.role == MdRole::Active,
+        };
+        taking_part.then_some(sync)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct MdMember {
+    /// `sdb1`
+    pub device: String,
+    pub role: MdRole,
+    /// A spare in sysfs that mdstat lists as a member, recovery writes to it
+    pub rebuilding: bool,
+    /// Whole disks under a partition or dm device
+    pub disks: Vec<String>,
+}
+
+impl MdMember {
+    /// `sda1[0](F)`
+    fn from_mdstat_word(word: &str) -> Option<Self> {
+        let (device, rest) = word.split_once('[')?;
+        let role = if rest.contains("(F)") {
+            MdRole::Faulty
+        } else if rest.contains("(S)") {
+            MdRole::Spare
+        } else {
+            MdRole::Active
+        };
+        Some(Self {
+            device: device.to_string(),
+            role,
+            rebuilding: false,
+            disks: Vec::new(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MdRole {
+    Active,
+    Spare,
+    Faulty,
+    Other(String),
+}
+
+impl MdRole {
+    /// Comma separated flags like `in_sync,write_mostly`
+    fn from_state(state: &str) -> Self {
+        let flags: Vec<&str> = state.split(',').collect();
+        if flags.contains(&"faulty") {
+            Self::Faulty
+        } else if flags.contains(&"spare") {
+            Self::Spare
+        } else if flags.contains(&"in_sync") {
+            Self::Active
+        } else {
+            Self::Other(state.to_string())
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Active => "active",
+            Self::Spare => "spare",
+            Self::Faulty => "faulty",
+            Self::Other(other) => other,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MdSync {
+    /// `recovery`, `resync`, `check` or `reshape`
+    pub action: String,
+    pub percent: Option<f64>,
+    /// `150.3min`
+    pub finish: Option<String>,
+}
+
+impl MdSync {
+    /// `[=>....]  recovery =  8.5% (166528000/1953382464) finish=150.3min speed=198144K/sec`
+    fn from_mdstat_line(line: &str) -> Option<Self> {
+        let (before, after) = line.split_once(" = ")?;
+        let action = before.split_whitespace().last()?.to_string();
+        let mut words = after.split_whitespace();
+        let percent = words
+            .next()
+            .and_then(|percent| percent.trim_end_matches('%').parse().ok());
+        let finish = words
+            .find_map(|word| word.strip_prefix("finish="))
+            .map(str::to_string);
+        Some(Self {
+            action,
+            percent,
+            finish,
+        })
+    }
+
+    /// `recovery 8.5% 150.3min to go`
+    pub fn describe(&self) -> String {
+        let mut res = self.action.clone();
+        if let Some(percent) = self.percent {
+            res.push_str(&format!(" {:.1}%", percent));
+        }
+        if let Some(finish) = &self.finish {
+            res.push_str(&format!(" {} to go", finish));
+        }
+        res
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{MdList, MdRole};
+    use crate::{blockdev::BlockResolver, sysfs::fixture::SysfsFixture};
+
+    const MDSTAT: &str = "Personalities : [raid1] [raid6] [raid5] [raid4]
+md1 : active raid5 sde[3](S) sdd[2] sdc[1] sdf[0]
+      5860270080 blocks super 1.2 level 5, 512k chunk, algorithm 2 [3/3] [UUU]
+      bitmap: 0/22 pages [0KB], 65536KB chunk
+
+md0 : active raid1 sdb1[2] sda1[0](F)
+      1953382464 blocks super 1.2 [2/1] [U_]
+      [=>...................]  recovery =  8.5% (166528000/1953382464) finish=150.3min speed=198144K/sec
+      bitmap: 0/15 pages [0KB], 65536KB chunk
+
+md127 : inactive sdg[0](S)
+      3906886488 blocks super 1.2
+
+unused devices: <none>
+";
+
+    #[test]
+    fn test_mdstat() {
+        let md_list = MdList::parse_mdstat(MDSTAT);
+        assert_eq!(md_list.arrays.len(), 3);
+
+        let md1 = &md_list.arrays[0];
+        assert_eq!(md1.level.as_deref(), Some("raid5"));
+        assert_eq!(md1.members.len(), 4);
+        assert_eq!(md1.members[0].role, MdRole::Spare);
+        assert_eq!(md1.sync, None);
+
+        let md0 = &md_list.arrays[1];
+        assert_eq!(md0.members[1].role, MdRole::Faulty);
+        assert_eq!(
+            md0.sync.as_ref().unwrap().describe(),
+            "recovery 8.5% 150.3min to go"
+        );
+
+        let md127 = &md_list.arrays[2];
+        assert_eq!(md127.state, "inactive");
+        assert_eq!(md127.level, None);
+        assert_eq!(md127.members[0].device, "sdg");
+    }
+
+    #[test]
+    fn test_member_states() {
+        let fixture = SysfsFixture::new("md");
+        fixture
+            .file("devices/host0/block/sda/sda1/partition", "1")
+            .file("devices/host0/block/sdb/sdb1/partition", "1")
+            .symlink("class/block/sda1", "../../devices/host0/block/sda/sda1")
+            .symlink("class/block/sdb1", "../../devices/host0/block/sdb/sdb1")
+            .file("class/block/md0/md/dev-sda1/state", "faulty")
+            .file("class/block/md0/md/dev-sdb1/state", "spare\n")
+            .host_file("proc/mdstat", MDSTAT);
+        let sysfs = fixture.sysfs();
+        let md_list = MdList::load(&sysfs, &BlockResolver::from_sysfs(&sysfs)).unwrap();
+
+        // rebuilding onto sdb1
+        let (md0, sdb1) = md_list.find_member("sdb").unwrap();
+        assert_eq!(md0.name, "md0");
+        assert_eq!(sdb1.device, "sdb1");
+        assert_eq!(sdb1.role, MdRole::Spare);
+        assert!(md0.sync_of(sdb1).is_some());
+        let (_, sda1) = md_list.find_member("sda").unwrap();
+        assert_eq!(sda1.role, MdRole::Faulty);
+        assert_eq!(md0.sync_of(sda1), None);
+        assert_eq!(md_list.find_member("sdd").unwrap().0.name, "md1");
+    }
+}
@@ -0,0 +1,151 @@
+use std::path::{Path, PathBuf};
+
+pub const DEFAULT_SYSFS_ROOT: &str = "/sys";
+
+/// Where sysfs is mounted. Normally `/sys`,
+/// but can be `/host/sys` inside a container or a captured copy of another machine's tree.
+/// `dev` and `proc` are read next to it so a captured tree doesn't mix in the host
+#[derive(Debug, Clone, PartialEq)]
+pub struct SysfsRoot {
+    root: PathBuf,
+}
+
+impl SysfsRoot {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn enclosure_dir(&self) -> PathBuf {
+        self.root.join("class").join("enclosure")
+    }
+
+    /// Disks, partitions and dm devices, symlinks into `devices/`
+    pub fn block_dir(&self) -> PathBuf {
+        self.root.join("class").join("block")
+    }
+
+    /// One dir per mounted filesystem uuid
+    pub fn btrfs_dir(&self) -> PathBuf {
+        self.root.join("fs").join("btrfs")
+    }
+
+    /// `/host/dev` for `/host/sys`
+    pub fn dev_dir(&self) -> PathBuf {
+        self.host_root().join("dev")
+    }
+
+    /// `/host/proc` for `/host/sys`
+    pub fn proc_dir(&self) -> PathBuf {
+        self.host_root().join("proc")
+    }
+
+    fn host_root(&self) -> &Path {
+        self.root.parent().unwrap_or(Path::new("/"))
+    }
+}
+
+impl Default for SysfsRoot {
+    fn default() -> Self {
+        Self::new(DEFAULT_SYSFS_ROOT)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use super::SysfsRoot;
+
+    #[test]
+    fn test_host_dirs() {
+        let sysfs = SysfsRoot::default();
+        assert_eq!(sysfs.dev_dir(), Path::new("/dev"));
+        assert_eq!(sysfs.proc_dir(), Path::new("/proc"));
+
+        let sysfs = SysfsRoot::new("/host/sys");
+        assert_eq!(sysfs.dev_dir(), Path::new("/host/dev"));
+        assert_eq!(sysfs.proc_dir(), Path::new("/host/proc"));
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod fixture {
+    use std::{
+        fs::{create_dir_all, remove_dir_all, write},
+        path::{Path, PathBuf},
+    };
+
+    use super::SysfsRoot;
+
+    /// Throwaway `sys`, `dev` and `proc` next to each other under the temp dir, removed on drop.
+    /// Paths are relative to `sys` unless the method says host
+    pub struct SysfsFixture {
+        host_root: PathBuf,
+        root: PathBuf,
+    }
+
+    impl SysfsFixture {
+        pub fn new(name: &str) -> Self {
+            let host_root =
+                std::env::temp_dir().join(format!("shelf-viewer-{}-{}", name, std::process::id()));
+            if host_root.exists() {
+                remove_dir_all(&host_root).unwrap();
+            }
+            let root = host_root.join("sys");
+            create_dir_all(&root).unwrap();
+            Self { host_root, root }
+        }
+
+        pub fn sysfs(&self) -> SysfsRoot {
+            SysfsRoot::new(&self.root)
+        }
+
+        pub fn file(&self, path: impl AsRef<Path>, content: &str) -> &Self {
+            write_file(&self.root.join(path), content);
+            self
+        }
+
+        pub fn dir(&self, path: impl AsRef<Path>) -> &Self {
+            create_dir_all(self.root.join(path)).unwrap();
+            self
+        }
+
+        /// Relative `target` like sysfs uses
+        pub fn symlink(&self, path: impl AsRef<Path>, target: impl AsRef<Path>) -> &Self {
+            write_symlink(&self.root.join(path), target);
+            self
+        }
+
+        /// `dev/sdb1` or `proc/mdstat`
+        pub fn host_file(&self, path: impl AsRef<Path>, content: &str) -> &Self {
+            write_file(&self.host_root.join(path), content);
+            self
+        }
+
+        /// `dev/disk/by-id/...` links
+        pub fn host_symlink(&self, path: impl AsRef<Path>, target: impl AsRef<Path>) -> &Self {
+            write_symlink(&self.host_root.join(path), target);
+            self
+        }
+    }
+
+    fn write_file(path: &Path, content: &str) {
+        create_dir_all(path.parent().unwrap()).unwrap();
+        write(path, content).unwrap();
+    }
+
+    fn write_symlink(path: &Path, target: impl AsRef<Path>) {
+        create_dir_all(path.parent().unwrap()).unwrap();
+        std::os::unix::fs::symlink(target, path).unwrap();
+    }
+
+    impl Drop for SysfsFixture {
+        fn drop(&mut self) {
+            let _ = remove_dir_all(&self.host_root);
+        }
+    }
+}
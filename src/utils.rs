@@ -1,6 +1,7 @@
 use std::fs::read_to_string;
 use std::io::{self, ErrorKind};
 use std::process::Command;
+use std::time::Duration;
 use std::{
     fs::{read_dir, DirEntry},
     path::{Path, PathBuf},
@@ -53,20 +54,121 @@ pub fn execute_command(
     linux_command: &str,
     args: impl IntoIterator<Item = impl AsRef<str>>,
 ) -> String {
+    match try_execute_command(linux_command, args) {
+        Ok(out) => out,
+        Err(err) => panic!("{}", err),
+    }
+}
+
+/// Missing commands, failed exits and stderr output are [SError::Command]
+pub fn try_execute_command(
+    linux_command: &str,
+    args: impl IntoIterator<Item = impl AsRef<str>>,
+) -> SResult<String> {
+    let (out, stderr) = try_execute_command_with_stderr(linux_command, args)?;
+    if !stderr.is_empty() {
+        return Err(SError::Command {
+            command: linux_command.to_string(),
+            message: format!("stderr: {}", stderr),
+        });
+    }
+    Ok(out)
+}
+
+/// Like [try_execute_command] for commands that warn on stderr and still succeed,
+/// only missing commands and failed exits are errors
+pub fn try_execute_command_with_stderr(
+    linux_command: &str,
+    args: impl IntoIterator<Item = impl AsRef<str>>,
+) -> SResult<(String, String)> {
     let mut command = Command::new("/usr/bin/env");
 
     command.arg(linux_command);
     for arg in args {
         command.arg(arg.as_ref());
     }
-    let out = command.output().expect("failed to start command");
-    assert!(
-        out.status.success(),
-        "bad status exit for {}",
-        linux_command
-    );
-    if !out.stderr.is_empty() {
-        panic!("stderr: {}", String::from_utf8(out.stderr).unwrap());
+    let command_error = |message: String| SError::Command {
+        command: linux_command.to_string(),
+        message,
+    };
+    let out = command
+        .output()
+        .map_err(|err| command_error(format!("failed to start {}", err)))?;
+    if !out.status.success() {
+        return Err(command_error(format!(
+            "bad status exit {} stderr: {}",
+            out.status,
+            String::from_utf8_lossy(&out.stderr).trim()
+        )));
+    }
+    Ok((
+        String::from_utf8_lossy(&out.stdout).trim().to_string(),
+        String::from_utf8_lossy(&out.stderr).trim().to_string(),
+    ))
+}
+
+/// For screenshots, `tank` is `pool0` and `vg_data` is `vg0` everywhere it's shown
+#[derive(Debug, Default)]
+pub struct PrivacyNames {
+    /// (kind, real name) in the order they were first seen
+    names: Vec<(String, String)>,
+}
+
+impl PrivacyNames {
+    pub fn name(&mut self, kind: &str, name: &str) -> String {
+        let mut same_kind = self.names.iter().filter(|(k, _)| k == kind);
+        let pos = match same_kind.clone().position(|(_, n)| n == name) {
+            Some(pos) => pos,
+            None => {
+                let pos = same_kind.by_ref().count();
+                self.names.push((kind.to_string(), name.to_string()));
+                pos
+            }
+        };
+        format!("{}{}", kind, pos)
+    }
+}
+
+/// `90`, `30s`, `10m`, `2h`, `1d`
+pub fn parse_duration(value: &str) -> Option<Duration> {
+    let unit_pos = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(unit_pos);
+    let number: u64 = number.parse().ok()?;
+    let seconds = match unit {
+        "" | "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 60 * 60 * 24,
+        _ => return None,
+    };
+    number.checked_mul(seconds).map(Duration::from_secs)
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use super::{parse_duration, PrivacyNames};
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("90"), Some(Duration::from_secs(90)));
+        assert_eq!(parse_duration("30s"), Some(Duration::from_secs(30)));
+        assert_eq!(parse_duration("10m"), Some(Duration::from_secs(600)));
+        assert_eq!(parse_duration("2h"), Some(Duration::from_secs(7200)));
+        assert_eq!(parse_duration("m"), None);
+        assert_eq!(parse_duration("10y"), None);
+        assert_eq!(parse_duration("18446744073709551615d"), None);
+    }
+
+    #[test]
+    fn test_privacy_names() {
+        let mut names = PrivacyNames::default();
+        assert_eq!(names.name("pool", "tank"), "pool0");
+        assert_eq!(names.name("vg", "vg_data"), "vg0");
+        assert_eq!(names.name("pool", "backup"), "pool1");
+        assert_eq!(names.name("pool", "tank"), "pool0");
     }
-    String::from_utf8(out.stdout).unwrap().trim().to_string()
 }
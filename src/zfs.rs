@@ -1,75 +1,1319 @@
-use crate::utils::execute_command;
+use std::time::{SystemTime, UNIX_EPOCH};
 
-#[derive(Default)]
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+use crate::{
+    blockdev::{is_on_disk, BlockResolver},
+    err::{SError, SResult},
+    utils::try_execute_command,
+};
+
+#[derive(Debug, Default, PartialEq)]
 pub struct ZfsList {
     pub pools: Vec<ZfsListPool>,
 }
 impl ZfsList {
-    pub fn execute() -> Self {
-        let res = execute_command(
+    /// JSON from OpenZFS 2.3+, otherwise the status text, otherwise the `zpool list` names
+    pub fn execute(resolver: &BlockResolver) -> SResult<Self> {
+        let json = try_execute_command(
             "zpool",
-            &[
-                "list", //
-                "-v",   // show underlying vdevs
-                "-L",   // use sane block device names
-                "-H",   // scripting mode
+            [
+                "status",     //
+                "-j",         // json
+                "--json-int", // counters as numbers
+                "-P",         // full device paths, same names as the text fallback
+                "-L",         // use sane block device names
             ],
         );
-        let mut zfslist = ZfsList::default();
+        let mut zfslist = match json {
+            Ok(res) => Self::parse_status_json(&res)?,
+            // unknown option before 2.3, anything else is a real failure
+            Err(SError::Command { message, .. }) if message.contains("invalid option") => {
+                Self::execute_text()?
+            }
+            Err(err) => return Err(err),
+        };
+        zfslist.resolve_disks(resolver);
 
-        let mut current_pool = ZfsListPool::default();
-        for (i, line) in res.split("\n").enumerate() {
-            if !line.starts_with("\t") {
-                if i != 0 {
-                    zfslist.pools.push(current_pool);
-                    current_pool = ZfsListPool::default();
-                }
+        // already tab separated exact bytes on every version, no need for `zpool list -j`
+        let res = try_execute_command(
+            "zpool",
+            [
+                "list", //
+                "-H",   // no header, tab separated
+                "-p",   // exact bytes
+                "-o",
+                "name,size,alloc,free,frag,cap,health",
+            ],
+        )?;
+        zfslist.parse_list(&res)?;
+        Ok(zfslist)
+    }
 
-                let mut parts = line.split("\t");
-                current_pool.pool_name = parts.next().unwrap().to_string();
-            } else {
-                current_pool.vdevs.push(ZfsListVDev::from_vdev_line(line))
-            }
-            // println!("{}", line);
-        }
+    /// Printed by the caller, after `--privacy` had a chance to rename the pools
+    pub fn print_summary(&self) {
         println!(
-            "'zpool list' found {} pools ({}) with {} vdevs",
-            zfslist.pools.len(),
-            zfslist
-                .pools
+            "'zpool status' found {} pools ({}) with {} vdevs",
+            self.pools.len(),
+            self.pools
                 .iter()
                 .map(|pool| pool.pool_name.as_str())
                 .collect::<Vec<&str>>()
                 .join(", "),
-            zfslist
-                .pools
+            self.pools
                 .iter()
-                .map(|pool| pool.vdevs.len())
+                .map(|pool| pool.all_vdevs().len())
                 .sum::<usize>()
         );
-        zfslist
     }
+
+    fn execute_text() -> SResult<Self> {
+        let res = try_execute_command(
+            "zpool",
+            [
+                "status", //
+                "-P",     // full device paths
+                "-L",     // use sane block device names
+                "-p",     // exact error counters
+            ],
+        )?;
+        match Self::parse_status(&res) {
+            Ok(zfslist) => Ok(zfslist),
+            Err(err @ SError::ZfsOutput { .. }) => {
+                println!("[E] {}, falling back to zpool list", err);
+                let res = try_execute_command(
+                    "zpool",
+                    [
+                        "list", //
+                        "-v",   // show underlying vdevs
+                        "-P",   // full device paths
+                        "-L",   // use sane block device names
+                        "-H",   // scripting mode
+                    ],
+                )?;
+                Self::parse_list_vdevs(&res)
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Names only from `zpool list -v -H`. Scripting mode drops the indent,
+    /// so disks go under the group vdev before them
+    fn parse_list_vdevs(res: &str) -> SResult<Self> {
+        let mut zfslist = ZfsList::default();
+        // (depth, vdev) like the status config tree
+        let mut config_lines: Vec<(usize, ZfsListVDev)> = Vec::new();
+        let mut in_group = false;
+        for line in res.lines().filter(|line| !line.trim().is_empty()) {
+            let vdev_line = line.strip_prefix('\t');
+            let name = vdev_line
+                .unwrap_or(line)
+                .split('\t')
+                .next()
+                .unwrap_or_default()
+                .trim();
+            // logs, cache and spares headers may not be indented
+            let is_pool = vdev_line.is_none() && VdevRole::from_section(name).is_none();
+            if name.is_empty() {
+                return Err(SError::ZfsOutput {
+                    message: format!("zpool list line {}", line),
+                });
+            }
+            let vdev = ZfsListVDev {
+                vdev_name: name.to_string(),
+                ..ZfsListVDev::default()
+            };
+            if is_pool {
+                zfslist.push_pool(&mut config_lines);
+                zfslist.pools.push(ZfsListPool {
+                    pool_name: name.to_string(),
+                    ..ZfsListPool::default()
+                });
+            }
+            let depth = if is_pool || VdevRole::from_section(name).is_some() {
+                in_group = false;
+                0
+            } else if vdev.is_group() {
+                in_group = true;
+                1
+            } else if in_group {
+                2
+            } else {
+                1
+            };
+            config_lines.push((depth, vdev));
+        }
+        zfslist.push_pool(&mut config_lines);
+        Ok(zfslist)
+    }
+
+    /// The `config:` tree of every pool. Indent is 2 spaces per level after a tab
+    fn parse_status(res: &str) -> SResult<Self> {
+        let mut zfslist = ZfsList::default();
+
+        let mut in_config = false;
+        // (depth, vdev) in output order
+        let mut config_lines: Vec<(usize, ZfsListVDev)> = Vec::new();
+        // scan: and its tab indented continuation lines, until the next `key:` header
+        let mut scan: Option<String> = None;
+        let mut in_scan = false;
+        for line in res.split("\n") {
+            let trimmed = line.trim();
+            let is_header = !line.starts_with('\t')
+                && trimmed.split_once(':').is_some_and(|(key, _)| {
+                    !key.is_empty() && key.chars().all(|c| c.is_ascii_alphabetic())
+                });
+            if is_header {
+                in_scan = false;
+            }
+            if let Some(pool_name) = trimmed.strip_prefix("pool:") {
+                zfslist.push_pool(&mut config_lines);
+                zfslist.pools.push(ZfsListPool {
+                    pool_name: pool_name.trim().to_string(),
+                    ..ZfsListPool::default()
+                });
+                in_config = false;
+                scan = None;
+            } else if let Some(scan_line) = trimmed.strip_prefix("scan:") {
+                scan = Some(scan_line.to_string());
+                in_scan = true;
+            } else if let (Some(scan), true) = (&mut scan, in_scan && line.starts_with('\t')) {
+                scan.push(' ');
+                scan.push_str(trimmed);
+            } else if trimmed == "config:" {
+                in_config = true;
+                if let (Some(pool), Some(scan)) = (zfslist.pools.last_mut(), scan.take()) {
+                    pool.scan = ScanProgress::from_status(&scan);
+                }
+            } else if in_config && !trimmed.is_empty() {
+                if trimmed.starts_with("NAME") {
+                    continue;
+                }
+                let Some(indented) = line.strip_prefix("\t") else {
+                    // errors: and the rest
+                    in_config = false;
+                    continue;
+                };
+                let depth = (indented.len() - indented.trim_start().len()) / 2;
+                config_lines.push((depth, ZfsListVDev::from_status_line(trimmed)?));
+            } else if in_config && !config_lines.is_empty() {
+                in_config = false;
+            }
+        }
+        zfslist.push_pool(&mut config_lines);
+        Ok(zfslist)
+    }
+
+    /// `zpool status -j`, vdevs are keyed by name in output order
+    fn parse_status_json(res: &str) -> SResult<Self> {
+        let status: StatusJson = serde_json::from_str(res).map_err(|err| SError::ZfsOutput {
+            message: format!("zpool status -j {}", err),
+        })?;
+        let mut zfslist = ZfsList::default();
+        for (pool_name, pool_json) in status.pools {
+            let pool_json: PoolJson =
+                serde_json::from_value(pool_json).map_err(|err| SError::ZfsOutput {
+                    message: format!("zpool status -j pool {} {}", pool_name, err),
+                })?;
+            let mut pool = ZfsListPool {
+                scan: pool_json
+                    .scan_stats
+                    .and_then(|scan_stats| ScanProgress::from_json(scan_stats, now_secs())),
+                pool_name,
+                ..ZfsListPool::default()
+            };
+            for (name, value) in pool_json.vdevs {
+                let root = ZfsListVDev::from_json(name, value, None, VdevRole::Data)?;
+                if root.vdev_name == pool.pool_name {
+                    pool.state = root.state;
+                    pool.errors = root.errors;
+                    pool.vdevs.extend(root.children.into_iter().map(|mut vdev| {
+                        vdev.parent = None;
+                        vdev
+                    }));
+                } else {
+                    pool.vdevs.push(root);
+                }
+            }
+            for (section_name, section) in pool_json.sections {
+                // logs, l2cache, spares... are objects of vdevs
+                let (Some(role), Value::Object(section)) =
+                    (VdevRole::from_section(&section_name), section)
+                else {
+                    continue;
+                };
+                for (name, value) in section {
+                    pool.vdevs
+                        .push(ZfsListVDev::from_json(name, value, None, role)?);
+                }
+            }
+            zfslist.pools.push(pool);
+        }
+        Ok(zfslist)
+    }
+
+    /// `zpool list -H -p`, joined to the pools from status by name
+    fn parse_list(&mut self, res: &str) -> SResult<()> {
+        for line in res.lines().filter(|line| !line.trim().is_empty()) {
+            let columns: Vec<&str> = line.split('\t').collect();
+            let [name, size, alloc, free, frag, cap, health] = columns[..] else {
+                return Err(SError::ZfsOutput {
+                    message: format!("zpool list line {}", line),
+                });
+            };
+            let bytes = |value: &str| {
+                value.parse::<u64>().map_err(|_| SError::ZfsOutput {
+                    message: format!("zpool list value {} in {}", value, line),
+                })
+            };
+            // - when unknown, % without -p
+            let percent = |value: &str| value.trim_end_matches('%').parse::<u64>().ok();
+            let usage = PoolUsage {
+                size: bytes(size)?,
+                alloc: bytes(alloc)?,
+                free: bytes(free)?,
+                frag: percent(frag),
+                cap: percent(cap),
+                health: VdevState::from_status(health),
+            };
+            match self.pools.iter_mut().find(|pool| pool.pool_name == name) {
+                Some(pool) => pool.usage = Some(usage),
+                None => self.pools.push(ZfsListPool {
+                    pool_name: name.to_string(),
+                    usage: Some(usage),
+                    ..ZfsListPool::default()
+                }),
+            }
+        }
+        Ok(())
+    }
+
+    fn push_pool(&mut self, config_lines: &mut Vec<(usize, ZfsListVDev)>) {
+        let Some(pool) = self.pools.last_mut() else {
+            return;
+        };
+        let mut lines = config_lines.drain(..).peekable();
+        while let Some((_, root)) = lines.next() {
+            let mut children = build_tree(&mut lines, 1, None);
+            // logs, cache, spares... headers are at the same level as the pool
+            let role = if root.vdev_name == pool.pool_name {
+                pool.state = root.state;
+                pool.errors = root.errors;
+                VdevRole::Data
+            } else {
+                VdevRole::from_section(&root.vdev_name).unwrap_or(VdevRole::Data)
+            };
+            for child in &mut children {
+                child.set_role(role);
+            }
+            pool.vdevs.extend(children);
+        }
+    }
+
+    /// Fill in the whole disks under every leaf, pools are often built on partitions or by-id links
+    pub fn resolve_disks(&mut self, resolver: &BlockResolver) {
+        for pool in &mut self.pools {
+            for vdev in &mut pool.vdevs {
+                vdev.resolve_disks(resolver);
+            }
+        }
+    }
+
+    /// By vdev name or the whole disk under it
+    pub fn find_vdev(&self, device: &str) -> Option<(&ZfsListPool, &ZfsListVDev)> {
+        self.pools.iter().find_map(|pool| {
+            pool.all_vdevs()
+                .into_iter()
+                .find(|vdev| is_on_disk(&vdev.vdev_name, &vdev.disks, device))
+                .map(|vdev| (pool, vdev))
+        })
+    }
+
+    /// Disks of a pool `tank`, a group vdev `raidz2-1`, or `tank/raidz2-1`
+    ///
+    /// A bare vdev name found in more than one pool is an error
+    pub fn leaf_names(&self, name: &str) -> SResult<Vec<&str>> {
+        let (pool_name, vdev_name) = match name.split_once('/') {
+            Some((pool_name, vdev_name)) => (Some(pool_name), Some(vdev_name)),
+            None if self.pools.iter().any(|pool| pool.pool_name == name) => (Some(name), None),
+            None => (None, Some(name)),
+        };
+        let pools: Vec<&ZfsListPool> = self
+            .pools
+            .iter()
+            .filter(|pool| pool_name.is_none_or(|pool_name| pool.pool_name == pool_name))
+            .filter(|pool| {
+                vdev_name.is_none_or(|vdev_name| {
+                    pool.all_vdevs()
+                        .iter()
+                        .any(|vdev| vdev.vdev_name == vdev_name)
+                })
+            })
+            .collect();
+        if pool_name.is_none() && pools.len() > 1 {
+            return Err(SError::AmbiguousVdev {
+                name: name.to_string(),
+                pools: pools
+                    .iter()
+                    .map(|pool| pool.pool_name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(","),
+            });
+        }
+        Ok(pools
+            .into_iter()
+            .flat_map(|pool| match vdev_name {
+                Some(vdev_name) => pool
+                    .all_vdevs()
+                    .into_iter()
+                    .filter(|vdev| vdev.vdev_name == vdev_name)
+                    .flat_map(|vdev| vdev.leaves())
+                    .collect(),
+                None => pool.leaves(),
+            })
+            .flat_map(|vdev| vdev.disk_names())
+            .fold(Vec::new(), |mut names, name| {
+                // spares in use are listed twice
+                if !names.contains(&name) {
+                    names.push(name);
+                }
+                names
+            }))
+    }
+}
+
+/// Lines at `depth` and everything nested under them
+fn build_tree(
+    lines: &mut std::iter::Peekable<impl Iterator<Item = (usize, ZfsListVDev)>>,
+    depth: usize,
+    parent: Option<&str>,
+) -> Vec<ZfsListVDev> {
+    let mut vdevs = Vec::new();
+    while let Some((line_depth, _)) = lines.peek() {
+        if *line_depth < depth {
+            break;
+        }
+        let (_, mut vdev) = lines.next().unwrap();
+        vdev.parent = parent.map(str::to_string);
+        vdev.children = build_tree(lines, depth + 1, Some(&vdev.vdev_name));
+        vdevs.push(vdev);
+    }
+    vdevs
 }
 
-#[derive(Default)]
+const GROUP_PREFIXES: [&str; 6] = [
+    "mirror-",
+    "raidz",
+    "draid",
+    "replacing-",
+    "spare-",
+    "indirect-",
+];
 
+#[derive(Debug, Default, PartialEq)]
 pub struct ZfsListPool {
     pub pool_name: String,
+    pub state: Option<VdevState>,
+    pub errors: Option<VdevErrors>,
+    /// Scrub or resilver running now
+    pub scan: Option<ScanProgress>,
+    /// From `zpool list`
+    pub usage: Option<PoolUsage>,
+    /// Top level vdevs
     pub vdevs: Vec<ZfsListVDev>,
 }
 
-#[derive(Default)]
+impl ZfsListPool {
+    /// Every vdev in the tree, parents before their children
+    pub fn all_vdevs(&self) -> Vec<&ZfsListVDev> {
+        let mut res = Vec::new();
+        for vdev in &self.vdevs {
+            vdev.walk(&mut res);
+        }
+        res
+    }
+
+    /// Every disk in the pool, including logs, cache and spares
+    pub fn leaves(&self) -> Vec<&ZfsListVDev> {
+        self.vdevs.iter().flat_map(|vdev| vdev.leaves()).collect()
+    }
+
+    /// Whole disks of every leaf, spares in use once
+    pub fn disk_names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = Vec::new();
+        for name in self.leaves().iter().flat_map(|vdev| vdev.disk_names()) {
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+        names
+    }
+
+    /// `log`, `spare`, `spare in use`... for anything but data disks.
+    /// A spare replacing a disk is listed under `spare-N` and in spares
+    pub fn role_tag(&self, vdev: &ZfsListVDev) -> Option<String> {
+        match self.spare(&vdev.vdev_name) {
+            Some(spare) if spare.state == Some(VdevState::Avail) => Some("spare".into()),
+            Some(_) => Some("spare in use".into()),
+            None if vdev.role == VdevRole::Data => None,
+            None => Some(vdev.role.name().into()),
+        }
+    }
+
+    /// Entry in the spares section
+    pub fn spare(&self, name: &str) -> Option<&ZfsListVDev> {
+        self.vdevs
+            .iter()
+            .find(|spare| spare.role == VdevRole::Spare && spare.vdev_name == name)
+    }
+
+    /// A scrub reads every disk holding data. A resilver only writes the marked disks,
+    /// or without marks the new disk of a `replacing-N` or `spare-N`
+    pub fn scan_of(&self, vdev: &ZfsListVDev) -> Option<&ScanProgress> {
+        let scan = self.scan.as_ref()?;
+        let affected = match scan.kind {
+            ScanKind::Scrub => !matches!(vdev.role, VdevRole::Cache | VdevRole::Spare),
+            ScanKind::Resilver if self.leaves().iter().any(|leaf| leaf.scanning) => vdev.scanning,
+            ScanKind::Resilver => {
+                let replacing = vdev.parent.as_deref().is_some_and(|parent| {
+                    parent.starts_with("replacing-") || parent.starts_with("spare-")
+                });
+                replacing && !vdev.state.as_ref().is_some_and(|state| state.is_failed())
+            }
+        };
+        (affected && vdev.children.is_empty()).then_some(scan)
+    }
+}
+
+#[derive(Debug, Default, PartialEq)]
 pub struct ZfsListVDev {
     pub vdev_name: String,
+    /// Group vdev like `mirror-0` this is in
+    pub parent: Option<String>,
+    pub state: Option<VdevState>,
+    /// Spares don't have counters
+    pub errors: Option<VdevErrors>,
+    pub role: VdevRole,
+    pub children: Vec<ZfsListVDev>,
+    /// Device path from the config, or `was /dev/sdb1` for a GUID after the disk vanished
+    pub path: Option<String>,
+    /// Whole disks under a leaf's partition, by-id link or dm device
+    pub disks: Vec<String>,
+    /// `(resilvering)` or `(repairing)` after the counters
+    pub scanning: bool,
 }
 
 impl ZfsListVDev {
-    fn from_vdev_line(line: &str) -> Self {
-        assert!(line.starts_with("\t"));
-        let line = line.trim_start();
-        let mut parts = line.split('\t');
-        Self {
-            vdev_name: parts.next().unwrap().to_string(),
+    /// `sdb  FAULTED  0  0  3  too many errors`, spares have a message instead of counters
+    fn from_status_line(line: &str) -> SResult<Self> {
+        let mut parts = line.split_whitespace();
+        let vdev_name = parts.next().unwrap_or_default().to_string();
+        let state = parts.next().map(VdevState::from_status);
+        let rest: Vec<&str> = parts.collect();
+        let counters = &rest[..rest.len().min(3)];
+        let path = rest
+            .windows(2)
+            .find(|words| words[0] == "was")
+            .map(|words| words[1].to_string());
+        let scanning = rest
+            .iter()
+            .any(|word| *word == "(resilvering)" || *word == "(repairing)");
+        let errors = match *counters {
+            [read, write, cksum] if read.parse::<u64>().is_ok() => {
+                let parse = |count: &str| {
+                    count.parse().map_err(|_| SError::ZfsOutput {
+                        message: format!("counter {} in {}", count, line),
+                    })
+                };
+                Some(VdevErrors {
+                    read: parse(read)?,
+                    write: parse(write)?,
+                    cksum: parse(cksum)?,
+                })
+            }
+            _ => None,
+        };
+        Ok(Self {
+            vdev_name,
+            parent: None,
+            state,
+            errors,
+            role: VdevRole::Data,
+            children: Vec::new(),
+            path,
+            disks: Vec::new(),
+            scanning,
+        })
+    }
+
+    fn from_json(
+        name: String,
+        value: Value,
+        parent: Option<&str>,
+        role: VdevRole,
+    ) -> SResult<Self> {
+        let vdev: VdevJson = serde_json::from_value(value).map_err(|err| SError::ZfsOutput {
+            message: format!("zpool status -j vdev {} {}", name, err),
+        })?;
+        // special and dedup are top level vdevs with a class
+        let role = vdev
+            .class
+            .as_deref()
+            .and_then(VdevRole::from_section)
+            .unwrap_or(role);
+        let errors = match (vdev.read_errors, vdev.write_errors, vdev.checksum_errors) {
+            (Some(read), Some(write), Some(cksum)) => Some(VdevErrors {
+                read: read.count(&name)?,
+                write: write.count(&name)?,
+                cksum: cksum.count(&name)?,
+            }),
+            _ => None,
+        };
+        let scanning = vdev
+            .scan_processed
+            .and_then(|processed| processed.count(&name).ok())
+            .is_some_and(|processed| processed != 0);
+        let children = vdev
+            .vdevs
+            .into_iter()
+            .map(|(child_name, child)| Self::from_json(child_name, child, Some(&name), role))
+            .collect::<SResult<Vec<Self>>>()?;
+        let vdev_name = vdev.name.unwrap_or(name);
+        // every disk has one, the text output only shows it when it isn't the name
+        let path = vdev.path.filter(|path| *path != vdev_name);
+        Ok(Self {
+            vdev_name,
+            parent: parent.map(str::to_string),
+            state: vdev.state.as_deref().map(VdevState::from_status),
+            errors,
+            role,
+            children,
+            path,
+            disks: Vec::new(),
+            scanning,
+        })
+    }
+
+    pub fn is_group(&self) -> bool {
+        GROUP_PREFIXES
+            .iter()
+            .any(|prefix| self.vdev_name.starts_with(prefix))
+    }
+
+    /// This vdev if it's a disk, otherwise every disk under it
+    pub fn leaves(&self) -> Vec<&ZfsListVDev> {
+        let mut res = Vec::new();
+        self.walk(&mut res);
+        res.retain(|vdev| vdev.children.is_empty() && !vdev.is_group());
+        res
+    }
+
+    /// Whole disks, or the vdev name before resolving
+    pub fn disk_names(&self) -> Vec<&str> {
+        if self.disks.is_empty() {
+            vec![self.vdev_name.as_str()]
+        } else {
+            self.disks.iter().map(String::as_str).collect()
+        }
+    }
+
+    fn resolve_disks(&mut self, resolver: &BlockResolver) {
+        if self.children.is_empty() && !self.is_group() {
+            // a GUID only means something through the path it was last seen at
+            let name = self.path.as_deref().unwrap_or(&self.vdev_name);
+            self.disks = resolver.whole_disks(name);
+        }
+        for child in &mut self.children {
+            child.resolve_disks(resolver);
+        }
+    }
+
+    fn set_role(&mut self, role: VdevRole) {
+        self.role = role;
+        for child in &mut self.children {
+            child.set_role(role);
+        }
+    }
+
+    fn walk<'s>(&'s self, res: &mut Vec<&'s ZfsListVDev>) {
+        res.push(self);
+        for child in &self.children {
+            child.walk(res);
+        }
+    }
+
+    /// `FAULTED 3 cksum`
+    pub fn health(&self) -> String {
+        let mut res = self
+            .state
+            .as_ref()
+            .map(|state| state.name().to_string())
+            .unwrap_or_default();
+        if let Some(errors) = &self.errors {
+            for (count, name) in [
+                (errors.read, "read"),
+                (errors.write, "write"),
+                (errors.cksum, "cksum"),
+            ] {
+                if count != 0 {
+                    res.push_str(&format!(" {} {}", count, name));
+                }
+            }
         }
+        res
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum VdevState {
+    Online,
+    Degraded,
+    Faulted,
+    Offline,
+    Unavail,
+    Removed,
+    /// Idle spare
+    Avail,
+    /// Spare replacing a disk
+    InUse,
+    Other(String),
+}
+
+impl VdevState {
+    fn from_status(value: &str) -> Self {
+        match value {
+            "ONLINE" => Self::Online,
+            "DEGRADED" => Self::Degraded,
+            "FAULTED" => Self::Faulted,
+            "OFFLINE" => Self::Offline,
+            "UNAVAIL" => Self::Unavail,
+            "REMOVED" => Self::Removed,
+            "AVAIL" => Self::Avail,
+            "INUSE" => Self::InUse,
+            other => Self::Other(other.to_string()),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Online => "ONLINE",
+            Self::Degraded => "DEGRADED",
+            Self::Faulted => "FAULTED",
+            Self::Offline => "OFFLINE",
+            Self::Unavail => "UNAVAIL",
+            Self::Removed => "REMOVED",
+            Self::Avail => "AVAIL",
+            Self::InUse => "INUSE",
+            Self::Other(other) => other,
+        }
+    }
+
+    /// Needs someone to look at the disk
+    pub fn is_failed(&self) -> bool {
+        matches!(self, Self::Faulted | Self::Unavail | Self::Removed)
+    }
+}
+
+/// Which allocation class or section of the pool a vdev is in
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum VdevRole {
+    #[default]
+    Data,
+    Log,
+    Cache,
+    Special,
+    Dedup,
+    Spare,
+}
+
+impl VdevRole {
+    /// Section headers in `zpool status`, JSON section keys and vdev classes
+    fn from_section(value: &str) -> Option<Self> {
+        match value {
+            "normal" => Some(Self::Data),
+            "logs" | "log" => Some(Self::Log),
+            "cache" | "l2cache" => Some(Self::Cache),
+            "special" => Some(Self::Special),
+            "dedup" => Some(Self::Dedup),
+            "spares" | "spare" => Some(Self::Spare),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Data => "data",
+            Self::Log => "log",
+            Self::Cache => "cache",
+            Self::Special => "special",
+            Self::Dedup => "dedup",
+            Self::Spare => "spare",
+        }
+    }
+}
+
+/// SIZE, ALLOC, FREE, FRAG, CAP and HEALTH columns of `zpool list`
+#[derive(Debug, Clone, PartialEq)]
+pub struct PoolUsage {
+    pub size: u64,
+    pub alloc: u64,
+    pub free: u64,
+    /// Percent, unknown without spacemap_histogram
+    pub frag: Option<u64>,
+    pub cap: Option<u64>,
+    pub health: VdevState,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScanKind {
+    Scrub,
+    Resilver,
+}
+
+impl ScanKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Scrub => "scrub",
+            Self::Resilver => "resilver",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScanProgress {
+    pub kind: ScanKind,
+    pub percent: Option<f64>,
+    /// `03:25:10` or `1 days 03:25:10` like zpool prints it
+    pub eta: Option<String>,
+}
+
+impl ScanProgress {
+    /// `resilver in progress since ... 400G resilvered, 17.54% done, 03:25:10 to go`
+    fn from_status(scan: &str) -> Option<Self> {
+        let kind = Self::running_kind(scan.split_whitespace().next()?, scan)?;
+        let (before_done, after_done) = match scan.split_once("% done") {
+            Some(split) => split,
+            None => (scan, ""),
+        };
+        let percent = before_done
+            .rsplit([' ', ','])
+            .next()
+            .and_then(|percent| percent.parse().ok());
+        let eta = after_done
+            .trim_start_matches([',', ' '])
+            .split_once(" to go")
+            .map(|(eta, _)| eta.to_string());
+        Some(Self { kind, percent, eta })
+    }
+
+    /// `scan_stats` with `--json-int`, the ETA is from the rate of the current pass
+    fn from_json(scan: ScanStatsJson, now_secs: u64) -> Option<Self> {
+        if scan.state != "SCANNING" {
+            return None;
+        }
+        let kind = Self::running_kind(&scan.function.to_lowercase(), "in progress")?;
+        let number = |count: Option<JsonCount>| count.and_then(|count| count.count("scan").ok());
+        let to_examine = number(scan.to_examine);
+        let issued = number(scan.issued);
+        let percent = match (issued, to_examine) {
+            (Some(issued), Some(to_examine)) if to_examine != 0 => {
+                Some(issued as f64 * 100.0 / to_examine as f64)
+            }
+            _ => None,
+        };
+        let elapsed = number(scan.pass_start).map(|pass_start| {
+            now_secs
+                .saturating_sub(pass_start)
+                .saturating_sub(number(scan.scrub_spent_paused).unwrap_or(0))
+        });
+        let eta = match (
+            to_examine,
+            issued,
+            number(scan.issued_bytes_per_scan),
+            elapsed,
+        ) {
+            (Some(to_examine), Some(issued), Some(pass_issued), Some(elapsed))
+                if pass_issued != 0 && elapsed != 0 =>
+            {
+                let remaining = to_examine.saturating_sub(issued);
+                Some(format_duration(remaining * elapsed / pass_issued))
+            }
+            _ => None,
+        };
+        Some(Self { kind, percent, eta })
+    }
+
+    fn running_kind(function: &str, scan: &str) -> Option<ScanKind> {
+        if !scan.contains("in progress") {
+            return None;
+        }
+        match function {
+            "scrub" => Some(ScanKind::Scrub),
+            "resilver" => Some(ScanKind::Resilver),
+            _ => None,
+        }
+    }
+
+    /// `resilver 17.5% 03:25:10 to go`
+    pub fn describe(&self) -> String {
+        let mut res = self.kind.name().to_string();
+        if let Some(percent) = self.percent {
+            res.push_str(&format!(" {:.1}%", percent));
+        }
+        if let Some(eta) = &self.eta {
+            res.push_str(&format!(" {} to go", eta));
+        }
+        res
+    }
+}
+
+fn format_duration(secs: u64) -> String {
+    let days = secs / 86400;
+    let time = format!(
+        "{:02}:{:02}:{:02}",
+        secs % 86400 / 3600,
+        secs % 3600 / 60,
+        secs % 60
+    );
+    if days == 0 {
+        time
+    } else {
+        format!("{} days {}", days, time)
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|since| since.as_secs())
+        .unwrap_or(0)
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct VdevErrors {
+    pub read: u64,
+    pub write: u64,
+    pub cksum: u64,
+}
+
+#[derive(Deserialize)]
+struct StatusJson {
+    pools: Map<String, Value>,
+}
+
+#[derive(Deserialize)]
+struct PoolJson {
+    #[serde(default)]
+    vdevs: Map<String, Value>,
+    scan_stats: Option<ScanStatsJson>,
+    /// logs, l2cache, spares, plus scalars like state
+    #[serde(flatten)]
+    sections: Map<String, Value>,
+}
+
+#[derive(Deserialize)]
+struct VdevJson {
+    name: Option<String>,
+    state: Option<String>,
+    class: Option<String>,
+    path: Option<String>,
+    read_errors: Option<JsonCount>,
+    write_errors: Option<JsonCount>,
+    checksum_errors: Option<JsonCount>,
+    /// Bytes resilvered or repaired on this disk
+    scan_processed: Option<JsonCount>,
+    #[serde(default)]
+    vdevs: Map<String, Value>,
+}
+
+#[derive(Deserialize)]
+struct ScanStatsJson {
+    function: String,
+    state: String,
+    to_examine: Option<JsonCount>,
+    issued: Option<JsonCount>,
+    /// Unix time the current pass started
+    pass_start: Option<JsonCount>,
+    /// Issued since pass_start
+    issued_bytes_per_scan: Option<JsonCount>,
+    scrub_spent_paused: Option<JsonCount>,
+}
+
+/// Strings without `--json-int`
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonCount {
+    Number(u64),
+    Text(String),
+}
+
+impl JsonCount {
+    fn count(self, vdev_name: &str) -> SResult<u64> {
+        match self {
+            Self::Number(count) => Ok(count),
+            Self::Text(text) => text.parse().map_err(|_| SError::ZfsOutput {
+                message: format!("counter {} of {}", text, vdev_name),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ScanKind, ScanProgress, VdevErrors, VdevRole, VdevState, ZfsList};
+    use crate::{blockdev::BlockResolver, err::SError, sysfs::fixture::SysfsFixture};
+
+    const STATUS_OUTPUT: &str = "  pool: tank
+ state: DEGRADED
+status: One or more devices are faulted in response to persistent errors.
+action: Replace the faulted device, or use 'zpool clear' to mark the device
+\trepaired.
+  scan: scrub repaired 0B in 01:02:03 with 0 errors on Sun Oct 11 01:26:04 2026
+config:
+
+\tNAME         STATE     READ WRITE CKSUM
+\ttank         DEGRADED     0     0     0
+\t  mirror-0   DEGRADED     0     0     0
+\t    sda      ONLINE       0     0     0
+\t    sdb      FAULTED      0     0     3  too many errors
+\t  sdc        ONLINE       0     0     0
+\tlogs
+\t  nvme0n1    ONLINE       0     0     0
+\tspares
+\t  sdf        AVAIL
+
+errors: No known data errors
+
+  pool: backup
+ state: ONLINE
+config:
+
+\tNAME        STATE     READ WRITE CKSUM
+\tbackup      ONLINE       0     0     0
+\t  mirror-0  ONLINE       0     0     0
+\t    sdd     ONLINE       0     0     0
+\t    sde     ONLINE       0     0     0
+
+errors: No known data errors
+";
+
+    #[test]
+    fn test_leaf_names() {
+        let zfs_list = ZfsList::parse_status(STATUS_OUTPUT).unwrap();
+        assert_eq!(zfs_list.pools.len(), 2);
+        assert_eq!(
+            zfs_list.leaf_names("tank").unwrap(),
+            ["sda", "sdb", "sdc", "nvme0n1", "sdf"]
+        );
+        assert!(matches!(
+            zfs_list.leaf_names("mirror-0"),
+            Err(SError::AmbiguousVdev { pools, .. }) if pools == "tank,backup"
+        ));
+        assert_eq!(
+            zfs_list.leaf_names("backup/mirror-0").unwrap(),
+            ["sdd", "sde"]
+        );
+        assert_eq!(zfs_list.leaf_names("sdc").unwrap(), ["sdc"]);
+        assert_eq!(zfs_list.find_vdev("sde").unwrap().0.pool_name, "backup");
+    }
+
+    const STATUS_JSON: &str = r#"{
+  "output_version": {"command": "zpool status", "vers_major": 0, "vers_minor": 1},
+  "pools": {
+    "tank": {
+      "name": "tank",
+      "state": "DEGRADED",
+      "vdevs": {
+        "tank": {
+          "name": "tank", "vdev_type": "root", "state": "DEGRADED",
+          "read_errors": 0, "write_errors": 0, "checksum_errors": 0,
+          "vdevs": {
+            "mirror-0": {
+              "name": "mirror-0", "vdev_type": "mirror", "state": "DEGRADED",
+              "read_errors": 0, "write_errors": 0, "checksum_errors": 0,
+              "vdevs": {
+                "/dev/sda": {"name": "/dev/sda", "path": "/dev/sda", "vdev_type": "disk", "state": "ONLINE",
+                  "read_errors": 0, "write_errors": 0, "checksum_errors": 0},
+                "/dev/sdb": {"name": "/dev/sdb", "path": "/dev/sdb", "vdev_type": "disk", "state": "FAULTED",
+                  "read_errors": "0", "write_errors": "0", "checksum_errors": "3"}
+              }
+            },
+            "/dev/sdc": {"name": "/dev/sdc", "path": "/dev/sdc", "vdev_type": "disk", "state": "ONLINE",
+              "read_errors": 0, "write_errors": 0, "checksum_errors": 0}
+          }
+        }
+      },
+      "logs": {
+        "/dev/nvme0n1": {"name": "/dev/nvme0n1", "path": "/dev/nvme0n1", "vdev_type": "disk", "state": "ONLINE",
+          "read_errors": 0, "write_errors": 0, "checksum_errors": 0}
+      },
+      "spares": {
+        "/dev/sdf": {"name": "/dev/sdf", "path": "/dev/sdf", "vdev_type": "disk", "state": "AVAIL"}
+      },
+      "error_count": 0
+    },
+    "backup": {
+      "name": "backup",
+      "state": "ONLINE",
+      "vdevs": {
+        "backup": {
+          "name": "backup", "vdev_type": "root", "state": "ONLINE",
+          "read_errors": 0, "write_errors": 0, "checksum_errors": 0,
+          "vdevs": {
+            "mirror-0": {
+              "name": "mirror-0", "vdev_type": "mirror", "state": "ONLINE",
+              "read_errors": 0, "write_errors": 0, "checksum_errors": 0,
+              "vdevs": {
+                "/dev/sdd": {"name": "/dev/sdd", "path": "/dev/sdd", "vdev_type": "disk", "state": "ONLINE",
+                  "read_errors": 0, "write_errors": 0, "checksum_errors": 0},
+                "/dev/sde": {"name": "/dev/sde", "path": "/dev/sde", "vdev_type": "disk", "state": "ONLINE",
+                  "read_errors": 0, "write_errors": 0, "checksum_errors": 0}
+              }
+            }
+          }
+        }
+      },
+      "error_count": 0
+    }
+  }
+}"#;
+
+    #[test]
+    fn test_roles() {
+        let zfs_list = ZfsList::parse_status(STATUS_OUTPUT).unwrap();
+        let tag = |device: &str| {
+            let (pool, vdev) = zfs_list.find_vdev(device).unwrap();
+            pool.role_tag(vdev)
+        };
+        assert_eq!(tag("sdb"), None);
+        assert_eq!(tag("nvme0n1").as_deref(), Some("log"));
+        assert_eq!(tag("sdf").as_deref(), Some("spare"));
+
+        let zfs_list = ZfsList::parse_status(
+            "  pool: tank
+config:
+
+\tNAME           STATE     READ WRITE CKSUM
+\ttank           DEGRADED     0     0     0
+\t  mirror-0     DEGRADED     0     0     0
+\t    sda        ONLINE       0     0     0
+\t    spare-1    DEGRADED     0     0     0
+\t      sdb      FAULTED      0     0     0  too many errors
+\t      sdf      ONLINE       0     0     0
+\tspecial
+\t  nvme1n1      ONLINE       0     0     0
+\tcache
+\t  nvme2n1      ONLINE       0     0     0
+\tspares
+\t  sdf          INUSE     currently in use
+",
+        )
+        .unwrap();
+        let (pool, sdf) = zfs_list.find_vdev("sdf").unwrap();
+        assert_eq!(sdf.parent.as_deref(), Some("spare-1"));
+        assert_eq!(pool.role_tag(sdf).as_deref(), Some("spare in use"));
+        assert_eq!(
+            zfs_list.leaf_names("tank").unwrap(),
+            ["sda", "sdb", "sdf", "nvme1n1", "nvme2n1"]
+        );
+        let (_, nvme1n1) = zfs_list.find_vdev("nvme1n1").unwrap();
+        assert_eq!(nvme1n1.role, VdevRole::Special);
+        let (_, nvme2n1) = zfs_list.find_vdev("nvme2n1").unwrap();
+        assert_eq!(nvme2n1.role, VdevRole::Cache);
+    }
+
+    #[test]
+    fn test_status_json() {
+        // both are run with -P
+        let status_paths = STATUS_OUTPUT
+            .replace(" sd", " /dev/sd")
+            .replace(" nvme", " /dev/nvme");
+        let zfs_list = ZfsList::parse_status_json(STATUS_JSON).unwrap();
+        assert_eq!(zfs_list, ZfsList::parse_status(&status_paths).unwrap());
+        assert_eq!(zfs_list.find_vdev("sdb").unwrap().1.vdev_name, "/dev/sdb");
+        assert_eq!(zfs_list.find_vdev("sdb").unwrap().1.path, None);
+        assert!(ZfsList::parse_status_json("{\"pools\": []}").is_err());
+        assert!(ZfsList::parse_status("  pool: tank\nconfig:\n\tsdb  ONLINE  0  x  0").is_err());
+    }
+
+    #[test]
+    fn test_partitions() {
+        let fixture = SysfsFixture::new("zfs-partitions");
+        for (disk, part) in [("sda", "sda1"), ("sdb", "sdb1")] {
+            fixture
+                .file(format!("devices/host0/block/{disk}/{part}/partition"), "1")
+                .symlink(
+                    format!("class/block/{disk}"),
+                    format!("../../devices/host0/block/{disk}"),
+                )
+                .symlink(
+                    format!("class/block/{part}"),
+                    format!("../../devices/host0/block/{disk}/{part}"),
+                );
+        }
+        let resolver = BlockResolver::from_sysfs(&fixture.sysfs());
+
+        let mut zfs_list = ZfsList::parse_status(
+            "  pool: tank
+config:
+
+\tNAME                  STATE     READ WRITE CKSUM
+\ttank                  DEGRADED     0     0     0
+\t  mirror-0            DEGRADED     0     0     0
+\t    sda1              ONLINE       0     0     0
+\t    9127365240912384  UNAVAIL      0     0     0  was /dev/sdb1
+",
+        )
+        .unwrap();
+        zfs_list.resolve_disks(&resolver);
+        assert_eq!(zfs_list.leaf_names("tank").unwrap(), ["sda", "sdb"]);
+        let (_, vdev) = zfs_list.find_vdev("sdb").unwrap();
+        assert_eq!(vdev.vdev_name, "9127365240912384");
+        assert_eq!(vdev.path.as_deref(), Some("/dev/sdb1"));
+        assert_eq!(zfs_list.find_vdev("sda").unwrap().1.vdev_name, "sda1");
+    }
+
+    #[test]
+    fn test_scan() {
+        let zfs_list = ZfsList::parse_status(
+            "  pool: tank
+ state: DEGRADED
+  scan: resilver in progress since Sun Oct 18 10:00:00 2026
+\t1.23T / 4.56T scanned at 500M/s, 800G / 4.56T issued at 300M/s
+\t400G resilvered, 17.54% done, 03:25:10 to go
+config:
+
+\tNAME             STATE     READ WRITE CKSUM
+\ttank             DEGRADED     0     0     0
+\t  mirror-0       DEGRADED     0     0     0
+\t    sda          ONLINE       0     0     0
+\t    replacing-1  DEGRADED     0     0     0
+\t      sdb        FAULTED      0     0     0  too many errors
+\t      sdg        ONLINE       0     0     0  (resilvering)
+",
+        )
+        .unwrap();
+        let pool = &zfs_list.pools[0];
+        let scan = pool.scan.as_ref().unwrap();
+        assert_eq!(scan.kind, ScanKind::Resilver);
+        assert_eq!(scan.describe(), "resilver 17.5% 03:25:10 to go");
+        let scan_of = |device: &str| pool.scan_of(zfs_list.find_vdev(device).unwrap().1);
+        assert!(scan_of("sdg").is_some());
+        assert!(scan_of("sda").is_none());
+        assert!(scan_of("sdb").is_none());
+
+        let scrub = ZfsList::parse_status(
+            "  pool: tank
+  scan: scrub in progress since Sun Oct 18 10:00:00 2026
+\t4.56T / 4.56T scanned, 1.2T / 4.56T issued at 1G/s
+\t0B repaired, 26.32% done, no estimated completion time
+remove: Removal of vdev 1 copied 1.2G in 0h1m, completed on Sat Oct 17 09:00:00 2026
+\t12.5K memory used for removed device mappings
+config:
+
+\tNAME        STATE     READ WRITE CKSUM
+\ttank        ONLINE       0     0     0
+\t  mirror-0  ONLINE       0     0     0
+\t    sda     ONLINE       0     0     0
+\t    sdb     ONLINE       0     0     0
+\tcache
+\t  nvme0n1   ONLINE       0     0     0
+",
+        )
+        .unwrap();
+        let pool = &scrub.pools[0];
+        assert_eq!(pool.scan.as_ref().unwrap().describe(), "scrub 26.3%");
+        assert!(pool.scan_of(scrub.find_vdev("sdb").unwrap().1).is_some());
+        assert!(pool
+            .scan_of(scrub.find_vdev("nvme0n1").unwrap().1)
+            .is_none());
+
+        // finished scans aren't progress
+        assert_eq!(
+            ZfsList::parse_status(STATUS_OUTPUT).unwrap().pools[0].scan,
+            None
+        );
+
+        let scan_stats = serde_json::from_str(
+            r#"{"function": "RESILVER", "state": "SCANNING", "to_examine": 4000,
+                "issued": 1000, "pass_start": 1000, "issued_bytes_per_scan": 500,
+                "scrub_spent_paused": 0}"#,
+        )
+        .unwrap();
+        let scan = ScanProgress::from_json(scan_stats, 1100).unwrap();
+        assert_eq!(scan.describe(), "resilver 25.0% 00:10:00 to go");
+    }
+
+    #[test]
+    fn test_list() {
+        let mut zfs_list = ZfsList::parse_status(STATUS_OUTPUT).unwrap();
+        zfs_list
+            .parse_list("tank\t4000787030016\t1200000000000\t2800787030016\t12\t30\tDEGRADED\nbackup\t1000\t0\t1000\t-\t0\tONLINE\n")
+            .unwrap();
+        let usage = zfs_list.pools[0].usage.as_ref().unwrap();
+        assert_eq!(usage.alloc, 1200000000000);
+        assert_eq!(usage.frag, Some(12));
+        assert_eq!(usage.cap, Some(30));
+        assert_eq!(usage.health, VdevState::Degraded);
+        assert_eq!(zfs_list.pools[1].usage.as_ref().unwrap().frag, None);
+        assert_eq!(
+            zfs_list.pools[0].disk_names(),
+            ["sda", "sdb", "sdc", "nvme0n1", "sdf"]
+        );
+        assert!(zfs_list.parse_list("tank\t4000\n").is_err());
+    }
+
+    #[test]
+    fn test_list_vdevs() {
+        let zfs_list = ZfsList::parse_list_vdevs(
+            "tank\t3.62T\t1.09T\t2.53T\t-\t-\t12%\t30%\t1.00x\tDEGRADED\t-
+\tmirror-0\t1.81T\t560G\t1.27T\t-\t-\t12%\t30.0%\t-\tDEGRADED
+\t/dev/sda1\t-\t-\t-\t-\t-\t-\t-\t-\tONLINE
+\t/dev/sdb1\t-\t-\t-\t-\t-\t-\t-\t-\tFAULTED
+logs\t-\t-\t-\t-\t-\t-\t-\t-\t-
+\t/dev/nvme0n1\t-\t-\t-\t-\t-\t-\t-\t-\tONLINE
+backup\t1000\t0\t1000\t-\t-\t-\t0%\t1.00x\tONLINE\t-
+\t/dev/sdf\t1000\t0\t1000\t-\t-\t-\t0%\t-\tONLINE
+",
+        )
+        .unwrap();
+        assert_eq!(zfs_list.pools.len(), 2);
+        assert_eq!(
+            zfs_list.leaf_names("tank/mirror-0").unwrap(),
+            ["/dev/sda1", "/dev/sdb1"]
+        );
+        let (tank, log) = zfs_list.find_vdev("nvme0n1").unwrap();
+        assert_eq!(tank.pool_name, "tank");
+        assert_eq!(log.role, VdevRole::Log);
+        assert_eq!(zfs_list.leaf_names("backup").unwrap(), ["/dev/sdf"]);
+        assert!(ZfsList::parse_list_vdevs("tank\n\t\t-\n").is_err());
+    }
+
+    #[test]
+    fn test_status_tree() {
+        let zfs_list = ZfsList::parse_status(STATUS_OUTPUT).unwrap();
+        let tank = &zfs_list.pools[0];
+        assert_eq!(tank.state, Some(VdevState::Degraded));
+        assert_eq!(tank.vdevs.len(), 4);
+        assert_eq!(tank.vdevs[0].children.len(), 2);
+
+        let (_, sdb) = zfs_list.find_vdev("sdb").unwrap();
+        assert_eq!(sdb.parent.as_deref(), Some("mirror-0"));
+        assert_eq!(sdb.state, Some(VdevState::Faulted));
+        assert_eq!(
+            sdb.errors,
+            Some(VdevErrors {
+                read: 0,
+                write: 0,
+                cksum: 3
+            })
+        );
+        assert_eq!(sdb.health(), "FAULTED 3 cksum");
+
+        let (_, sdf) = zfs_list.find_vdev("sdf").unwrap();
+        assert_eq!(sdf.state, Some(VdevState::Avail));
+        assert_eq!(sdf.errors, None);
+        assert_eq!(sdf.parent, None);
     }
 }